@@ -0,0 +1,13 @@
+// Compiles the gRPC service definition when the `grpc` feature is enabled
+//     - protoc is vendored so builds don't depend on a system install
+
+fn main() {
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/snowprints.proto");
+        let protoc = protoc_bin_vendored::protoc_bin_path().expect("vendored protoc is available");
+        std::env::set_var("PROTOC", protoc);
+        tonic_prost_build::compile_protos("proto/snowprints.proto")
+            .expect("proto/snowprints.proto compiles");
+    }
+}
@@ -0,0 +1,207 @@
+// Compose snowprints on a chosen logical volume instead of the rotation
+//     - sequences used on chosen logical volumes are tracked for the current ms only
+//     - the rotation skips past sequences already used when it lands on a logical volume
+//     - a chosen logical volume the rotation is on shares the rotation's sequence
+//     - strictly increasing settings move the rotation to the chosen logical volume instead
+
+// Within a ms the rotation only leaves a logical volume once all of its sequences
+// are used, so logical volumes behind the rotation are always exhausted.
+
+use crate::hash::mix;
+use crate::{
+    compose_from_settings_and_state, get_logical_volume, get_logical_volume_length,
+    modify_state_time_did_not_change, try_compose, Error, Settings, State, MAX_SEQUENCES,
+};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub(crate) struct VolumeSequences {
+    duration_ms: u64,
+    sequences: HashMap<u64, u64>,
+    is_exhausted: bool,
+}
+
+impl VolumeSequences {
+    // every logical volume is used up until duration_ms has passed
+    pub(crate) fn exhausted(duration_ms: u64) -> VolumeSequences {
+        VolumeSequences {
+            duration_ms,
+            sequences: HashMap::new(),
+            is_exhausted: true,
+        }
+    }
+
+    // the next unused sequence on a logical volume, 0 when untouched this ms
+    fn get(&self, duration_ms: u64, logical_volume: u64) -> u64 {
+        match (self.duration_ms == duration_ms, self.is_exhausted) {
+            (true, true) => MAX_SEQUENCES,
+            (true, _) => self.sequences.get(&logical_volume).copied().unwrap_or(0),
+            _ => 0,
+        }
+    }
+
+    // the most recent ms a chosen logical volume was composed on
+    pub(crate) fn duration_ms(&self) -> u64 {
+        self.duration_ms
+    }
+
+    fn set(&mut self, duration_ms: u64, logical_volume: u64, sequence: u64) {
+        if self.duration_ms != duration_ms {
+            self.duration_ms = duration_ms;
+            self.sequences.clear();
+            self.is_exhausted = false;
+        }
+        self.sequences.insert(logical_volume, sequence);
+    }
+
+    // how many sequences the rotation can use before landing on a touched logical volume
+    pub(crate) fn limit_available_sequences(
+        &self,
+        state: &State,
+        logical_volume_length: u64,
+        available: u64,
+    ) -> u64 {
+        if self.duration_ms != state.prev_duration_ms {
+            return available;
+        }
+
+        let remaining_logical_volumes = get_remaining_logical_volumes(state, logical_volume_length);
+        self.sequences
+            .keys()
+            .map(|&logical_volume| {
+                (logical_volume + logical_volume_length - state.logical_volume)
+                    % logical_volume_length
+            })
+            .filter(|&offset| 0 < offset && offset <= remaining_logical_volumes)
+            .map(|offset| MAX_SEQUENCES - 1 - state.sequence + (offset - 1) * MAX_SEQUENCES)
+            .fold(available, u64::min)
+    }
+}
+
+// stable across releases and platforms, keys must keep routing to the same logical volume
+pub(crate) fn hash_key(key: &[u8]) -> u64 {
+    let hash = key.iter().fold(FNV_OFFSET_BASIS, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+    });
+    mix(hash)
+}
+
+// compose_from_settings_and_state, but skipping sequences used by chosen logical volumes
+pub(crate) fn compose_from_settings_and_volume_state(
+    settings: &Settings,
+    state: &mut State,
+    volume_sequences: &VolumeSequences,
+    duration_ms: u64,
+) -> Result<u64, Error> {
+    let mut snowprint = compose_from_settings_and_state(settings, state, duration_ms)?;
+
+    // a sequence of 0 means the rotation just landed on a logical volume
+    while state.sequence == 0 {
+        let sequence = volume_sequences.get(duration_ms, state.logical_volume);
+        if sequence == 0 {
+            break;
+        }
+
+        state.sequence = sequence - 1;
+        modify_state_time_did_not_change(state, get_logical_volume_length(settings))?;
+        snowprint = try_compose(
+            duration_ms,
+            get_logical_volume(settings, state.logical_volume),
+            state.sequence,
+        )?;
+    }
+
+    Ok(snowprint)
+}
+
+// `logical_volume` is an index into the logical volumes in settings
+pub(crate) fn compose_on_logical_volume_from_settings_and_state(
+    settings: &Settings,
+    state: &mut State,
+    volume_sequences: &mut VolumeSequences,
+    duration_ms: u64,
+    logical_volume: u64,
+) -> Result<u64, Error> {
+    if settings.strictly_increasing {
+        return compose_on_logical_volume_strictly(settings, state, duration_ms, logical_volume);
+    }
+
+    let logical_volume_length = get_logical_volume_length(settings);
+
+    // the rotation has already used this ms
+    if state.prev_duration_ms == duration_ms {
+        if logical_volume == state.logical_volume {
+            if MAX_SEQUENCES <= state.sequence + 1 {
+                return Err(Error::ExceededAvailableSequences);
+            }
+            state.sequence += 1;
+            return try_compose(
+                duration_ms,
+                get_logical_volume(settings, logical_volume),
+                state.sequence,
+            );
+        }
+
+        let offset =
+            (logical_volume + logical_volume_length - state.logical_volume) % logical_volume_length;
+        let is_ahead = offset <= get_remaining_logical_volumes(state, logical_volume_length);
+        if !is_ahead && logical_volume != state.prev_logical_volume {
+            return Err(Error::ExceededAvailableSequences);
+        }
+    }
+
+    let sequence = volume_sequences.get(duration_ms, logical_volume);
+    if MAX_SEQUENCES <= sequence {
+        return Err(Error::ExceededAvailableSequences);
+    }
+    volume_sequences.set(duration_ms, logical_volume, sequence + 1);
+
+    try_compose(
+        duration_ms,
+        get_logical_volume(settings, logical_volume),
+        sequence,
+    )
+}
+
+// the rotation skips ahead to the chosen logical volume, the ones it skips stay unused this ms
+//     - a new ms starts on the chosen logical volume
+//     - a logical volume behind the rotation waits for the next ms
+fn compose_on_logical_volume_strictly(
+    settings: &Settings,
+    state: &mut State,
+    duration_ms: u64,
+    logical_volume: u64,
+) -> Result<u64, Error> {
+    match state.prev_duration_ms < duration_ms {
+        true => {
+            state.prev_duration_ms = duration_ms;
+            state.sequence = 0;
+            state.logical_volume = logical_volume;
+            state.prev_logical_volume = 0;
+        }
+        _ => match logical_volume.cmp(&state.logical_volume) {
+            Ordering::Greater => {
+                state.sequence = 0;
+                state.logical_volume = logical_volume;
+            }
+            Ordering::Equal if state.sequence + 1 < MAX_SEQUENCES => state.sequence += 1,
+            _ => return Err(Error::ExceededAvailableSequences),
+        },
+    }
+
+    try_compose(
+        duration_ms,
+        get_logical_volume(settings, logical_volume),
+        state.sequence,
+    )
+}
+
+// logical volumes the rotation can still move to this ms
+fn get_remaining_logical_volumes(state: &State, logical_volume_length: u64) -> u64 {
+    (state.prev_logical_volume + logical_volume_length - state.logical_volume - 1)
+        % logical_volume_length
+}
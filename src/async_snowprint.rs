@@ -0,0 +1,39 @@
+// An async Snowprint for runtimes that can't afford a blocking wait
+//     - shares the lock-free state of SyncSnowprint
+//     - yields to the runtime until the next ms instead of returning
+//       Error::ExceededAvailableSequences
+
+use crate::{Clock, Error, Settings, SnowprintId, SyncSnowprint, SystemClock};
+
+#[derive(Debug)]
+pub struct AsyncSnowprint<C: Clock = SystemClock> {
+    snowprint: SyncSnowprint<C>,
+}
+
+impl AsyncSnowprint {
+    pub fn new(settings: Settings) -> Result<AsyncSnowprint, Error> {
+        AsyncSnowprint::with_clock(settings, SystemClock)
+    }
+}
+
+impl<C: Clock> AsyncSnowprint<C> {
+    pub fn with_clock(settings: Settings, clock: C) -> Result<AsyncSnowprint<C>, Error> {
+        Ok(AsyncSnowprint {
+            snowprint: SyncSnowprint::with_clock(settings, clock)?,
+        })
+    }
+
+    pub async fn compose(&self) -> Result<SnowprintId, Error> {
+        loop {
+            let (duration_ms, snowprint) = self.snowprint.compose_once();
+            match snowprint {
+                Err(Error::ExceededAvailableSequences) => {
+                    if let Some(nap) = self.snowprint.get_nap_until_next_ms(duration_ms) {
+                        tokio::time::sleep(nap).await;
+                    }
+                }
+                snowprint => return snowprint.map(SnowprintId::from),
+            }
+        }
+    }
+}
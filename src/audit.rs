@@ -0,0 +1,266 @@
+// Watch snowprints from many generators for anything that breaks the guarantees
+//     - duplicates across every generator
+//     - ids that don't increase from one generator
+//     - logical volumes outside a generator's registered settings
+//     - timestamps ahead of the auditor's clock
+//     - with window_ms set, only the most recent ms of ids are kept for duplicate checks
+
+use crate::{get_logical_volume_index, Clock, Settings, SnowprintId, SystemClock};
+use std::collections::{BTreeMap, HashMap};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AuditSettings {
+    pub origin_system_time: SystemTime,
+    pub window_ms: Option<u64>,
+    pub max_future_ms: u64,
+}
+
+impl Default for AuditSettings {
+    fn default() -> AuditSettings {
+        AuditSettings {
+            origin_system_time: UNIX_EPOCH,
+            window_ms: None,
+            max_future_ms: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Finding {
+    Duplicate {
+        generator: String,
+        first_generator: String,
+        snowprint: SnowprintId,
+    },
+    NonMonotonic {
+        generator: String,
+        snowprint: SnowprintId,
+        previous: SnowprintId,
+    },
+    LogicalVolumeOutOfRange {
+        generator: String,
+        snowprint: SnowprintId,
+    },
+    FromFuture {
+        generator: String,
+        snowprint: SnowprintId,
+        ahead_by_ms: u64,
+    },
+}
+
+// unchecked counts ids that arrived after their ms left the window
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct AuditReport {
+    pub observed: u64,
+    pub duplicates: u64,
+    pub non_monotonic: u64,
+    pub out_of_range: u64,
+    pub from_future: u64,
+    pub unchecked: u64,
+}
+
+impl AuditReport {
+    pub fn is_clean(&self) -> bool {
+        self.duplicates == 0
+            && self.non_monotonic == 0
+            && self.out_of_range == 0
+            && self.from_future == 0
+    }
+}
+
+#[derive(Debug)]
+struct Generator {
+    settings: Option<Settings>,
+    previous: Option<SnowprintId>,
+}
+
+#[derive(Debug)]
+pub struct Auditor<C: Clock = SystemClock> {
+    settings: AuditSettings,
+    clock: C,
+    generators: Vec<Generator>,
+    generator_indices: HashMap<String, usize>,
+    generator_names: Vec<String>,
+    // ms -> snowprint -> index of the generator that emitted it first
+    windows: BTreeMap<u64, HashMap<u64, usize>>,
+    newest_ms: u64,
+    tracked: usize,
+    report: AuditReport,
+}
+
+impl Auditor {
+    pub fn new(settings: AuditSettings) -> Auditor {
+        Auditor::with_clock(settings, SystemClock)
+    }
+}
+
+impl<C: Clock> Auditor<C> {
+    // a window_ms of n keeps the n most recent ms, so 0 keeps 1
+    pub fn with_clock(settings: AuditSettings, clock: C) -> Auditor<C> {
+        Auditor {
+            settings: AuditSettings {
+                window_ms: settings.window_ms.map(|window_ms| window_ms.max(1)),
+                ..settings
+            },
+            clock,
+            generators: Vec::new(),
+            generator_indices: HashMap::new(),
+            generator_names: Vec::new(),
+            windows: BTreeMap::new(),
+            newest_ms: 0,
+            tracked: 0,
+            report: AuditReport::default(),
+        }
+    }
+
+    // generators that are never registered skip the logical volume check
+    pub fn register(&mut self, generator: &str, settings: &Settings) {
+        let index = self.get_generator_index(generator);
+        self.generators[index].settings = Some(settings.clone());
+    }
+
+    pub fn observe(&mut self, generator: &str, snowprint: SnowprintId) -> Vec<Finding> {
+        let index = self.get_generator_index(generator);
+        let mut findings = Vec::new();
+        self.report.observed += 1;
+
+        let horizon_ms = self.get_horizon_ms();
+        let ahead_by_ms = snowprint.timestamp_ms().saturating_sub(horizon_ms);
+        if 0 < ahead_by_ms {
+            findings.push(Finding::FromFuture {
+                generator: generator.to_string(),
+                snowprint,
+                ahead_by_ms,
+            });
+            self.report.from_future += 1;
+        }
+
+        if let Some(first_index) = self.track(index, snowprint, horizon_ms) {
+            findings.push(Finding::Duplicate {
+                generator: generator.to_string(),
+                first_generator: self.generator_names[first_index].clone(),
+                snowprint,
+            });
+            self.report.duplicates += 1;
+        }
+
+        let audited = &mut self.generators[index];
+        if let Some(previous) = audited.previous {
+            if snowprint <= previous {
+                findings.push(Finding::NonMonotonic {
+                    generator: generator.to_string(),
+                    snowprint,
+                    previous,
+                });
+                self.report.non_monotonic += 1;
+            }
+        }
+        audited.previous = Some(snowprint);
+
+        if let Some(settings) = &audited.settings {
+            if get_logical_volume_index(settings, snowprint.logical_volume()).is_none() {
+                findings.push(Finding::LogicalVolumeOutOfRange {
+                    generator: generator.to_string(),
+                    snowprint,
+                });
+                self.report.out_of_range += 1;
+            }
+        }
+
+        findings
+    }
+
+    pub fn report(&self) -> AuditReport {
+        self.report
+    }
+
+    // how many snowprints are held for duplicate checks
+    pub fn tracked(&self) -> usize {
+        self.tracked
+    }
+
+    fn get_generator_index(&mut self, generator: &str) -> usize {
+        if let Some(&index) = self.generator_indices.get(generator) {
+            return index;
+        }
+
+        let index = self.generators.len();
+        self.generators.push(Generator {
+            settings: None,
+            previous: None,
+        });
+        self.generator_indices.insert(generator.to_string(), index);
+        self.generator_names.push(generator.to_string());
+        index
+    }
+
+    // the latest ms that isn't from the future
+    fn get_horizon_ms(&self) -> u64 {
+        let now_ms = match self
+            .clock
+            .now()
+            .duration_since(self.settings.origin_system_time)
+        {
+            Ok(duration) => duration.as_millis() as u64,
+            _ => 0,
+        };
+        now_ms.saturating_add(self.settings.max_future_ms)
+    }
+
+    // returns the generator that first emitted the snowprint, if it was seen before
+    //     - ids from the future are kept, but they can't drag the window forward
+    //     - with a window, only ids up to window_ms past the horizon are kept
+    fn track(&mut self, index: usize, snowprint: SnowprintId, horizon_ms: u64) -> Option<usize> {
+        let timestamp_ms = snowprint.timestamp_ms();
+        if let Some(window_ms) = self.settings.window_ms {
+            if timestamp_ms.saturating_add(window_ms) <= self.newest_ms
+                || horizon_ms.saturating_add(window_ms) < timestamp_ms
+            {
+                self.report.unchecked += 1;
+                return None;
+            }
+        }
+
+        let window = self.windows.entry(timestamp_ms).or_default();
+        if let Some(&first_index) = window.get(&snowprint.into()) {
+            return Some(first_index);
+        }
+        window.insert(snowprint.into(), index);
+        self.tracked += 1;
+
+        if timestamp_ms <= horizon_ms && self.newest_ms < timestamp_ms {
+            self.newest_ms = timestamp_ms;
+        }
+        self.evict(horizon_ms);
+        None
+    }
+
+    fn evict(&mut self, horizon_ms: u64) {
+        let window_ms = match self.settings.window_ms {
+            Some(window_ms) => window_ms,
+            _ => return,
+        };
+        while let Some(entry) = self.windows.first_entry() {
+            if self.newest_ms < entry.key().saturating_add(window_ms) {
+                break;
+            }
+            self.tracked -= entry.remove().len();
+        }
+
+        // ids past newest_ms all came from the future, they leave as the horizon passes them
+        let future_end_ms = horizon_ms.saturating_sub(window_ms);
+        if self.newest_ms < future_end_ms {
+            let passed: Vec<u64> = self
+                .windows
+                .range(self.newest_ms + 1..=future_end_ms)
+                .map(|(&timestamp_ms, _)| timestamp_ms)
+                .collect();
+            for timestamp_ms in passed {
+                if let Some(window) = self.windows.remove(&timestamp_ms) {
+                    self.tracked -= window.len();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,65 @@
+// Serve snowprints over gRPC
+//     - snowprints-grpc-server <config.json>
+//     - SIGINT and SIGTERM end open lease streams then stop the server
+
+use snowprints::grpc::{GrpcConfig, GrpcServer};
+use std::process::ExitCode;
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let config = match args.as_slice() {
+        [path] if path != "--help" && path != "-h" => match GrpcConfig::from_file(path) {
+            Ok(config) => config,
+            Err(err) => {
+                eprintln!("snowprints-grpc-server: failed to read {}: {}", path, err);
+                return ExitCode::FAILURE;
+            }
+        },
+        _ => {
+            eprintln!("usage: snowprints-grpc-server <config.json>");
+            return ExitCode::from(2);
+        }
+    };
+
+    let server = match GrpcServer::bind(config).await {
+        Ok(server) => server,
+        Err(err) => {
+            eprintln!("snowprints-grpc-server: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Ok(address) = server.local_addr() {
+        eprintln!("snowprints-grpc-server: listening on {}", address);
+    }
+
+    match server.run(wait_for_signal()).await {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("snowprints-grpc-server: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    match signal(SignalKind::terminate()) {
+        Ok(mut terminate) => {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = terminate.recv() => {}
+            }
+        }
+        _ => {
+            let _ = tokio::signal::ctrl_c().await;
+        }
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() {
+    let _ = tokio::signal::ctrl_c().await;
+}
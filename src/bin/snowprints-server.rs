@@ -0,0 +1,70 @@
+// Serve snowprints over HTTP on localhost
+//     - snowprints-server <config.json>
+//     - SIGINT and SIGTERM stop the server after persisting the high water mark
+
+use snowprints::server::{Server, ServerConfig, ShutdownHandle};
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+static SIGNALED: AtomicBool = AtomicBool::new(false);
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let config = match args.as_slice() {
+        [path] if path != "--help" && path != "-h" => match ServerConfig::from_file(path) {
+            Ok(config) => config,
+            Err(err) => {
+                eprintln!("snowprints-server: failed to read {}: {}", path, err);
+                return ExitCode::FAILURE;
+            }
+        },
+        _ => {
+            eprintln!("usage: snowprints-server <config.json>");
+            return ExitCode::from(2);
+        }
+    };
+
+    let server = match Server::bind(config) {
+        Ok(server) => server,
+        Err(err) => {
+            eprintln!("snowprints-server: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+    if let Ok(address) = server.local_addr() {
+        eprintln!("snowprints-server: listening on http://{}", address);
+    }
+
+    watch_signals(server.shutdown_handle());
+    match server.run() {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("snowprints-server: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(unix)]
+extern "C" fn on_signal(_signal: libc::c_int) {
+    SIGNALED.store(true, Ordering::Release);
+}
+
+// signal handlers may only touch atomics, a thread passes the signal on
+fn watch_signals(shutdown_handle: ShutdownHandle) {
+    #[cfg(unix)]
+    unsafe {
+        let on_signal = on_signal as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        libc::signal(libc::SIGINT, on_signal);
+        libc::signal(libc::SIGTERM, on_signal);
+    }
+
+    thread::spawn(move || {
+        while !SIGNALED.load(Ordering::Acquire) {
+            thread::sleep(Duration::from_millis(50));
+        }
+        shutdown_handle.shutdown();
+    });
+}
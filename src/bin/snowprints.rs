@@ -0,0 +1,322 @@
+// Generate, decode, and inspect snowprints from the command line
+//     - arguments are parsed by hand to keep the crate free of dependencies
+//     - times are RFC 3339 timestamps or ms since the unix epoch
+//     - --json prints one json document instead of lines of text
+//     - generate keeps no state, two runs in the same ms on the same volumes print the same ids
+
+use snowprints::{
+    range_for, range_for_logical_volume, rfc3339, Encoding, ExhaustionPolicy, Settings, Snowprint,
+    SnowprintId,
+};
+use std::collections::HashMap;
+use std::process::ExitCode;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const USAGE: &str = "usage: snowprints <command> [options]
+
+commands:
+    generate                  compose new snowprints, runs in the same ms on the
+                              same volumes repeat ids, so give concurrent runs
+                              disjoint --base and --length
+        --base <n>            first logical volume, defaults to 0
+        --length <n>          number of logical volumes, defaults to 8192
+        --count <n>           number of snowprints, up to 4294967295, defaults to 1
+        --format <format>     decimal, base32, or base62, defaults to decimal
+    decode <id>               print the timestamp, logical volume, and sequence of a snowprint
+        --format <format>     format of <id>, defaults to decimal
+    encode <id>               convert a decimal snowprint to another format
+        --format <format>     defaults to base32
+    bounds <from> <to>        smallest and largest snowprints from <from> up to <to>
+        --volume <n>          narrow the bounds to one logical volume
+
+options:
+    --origin <time>           origin_system_time, defaults to the unix epoch
+    --json                    print json
+    --help                    print this message
+";
+
+struct Args {
+    command: String,
+    positional: Vec<String>,
+    options: HashMap<String, String>,
+    json: bool,
+}
+
+enum Failure {
+    Usage(String),
+    Runtime(String),
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() || args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        print!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    match parse_args(args).and_then(|args| run(&args)) {
+        Ok(output) => {
+            println!("{}", output);
+            ExitCode::SUCCESS
+        }
+        Err(Failure::Usage(message)) => {
+            eprintln!("snowprints: {}\n\n{}", message, USAGE);
+            ExitCode::from(2)
+        }
+        Err(Failure::Runtime(message)) => {
+            eprintln!("snowprints: {}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn parse_args(args: Vec<String>) -> Result<Args, Failure> {
+    let mut args = args.into_iter();
+    let command = args.next().unwrap_or_default();
+    let mut positional = Vec::new();
+    let mut options = HashMap::new();
+    let mut json = false;
+
+    while let Some(arg) = args.next() {
+        if arg == "--json" {
+            json = true;
+            continue;
+        }
+        let name = match arg.strip_prefix("--") {
+            Some(name) => name,
+            _ => {
+                positional.push(arg);
+                continue;
+            }
+        };
+
+        let (name, value) = match name.split_once('=') {
+            Some((name, value)) => (name.to_string(), value.to_string()),
+            _ => match args.next() {
+                Some(value) => (name.to_string(), value),
+                _ => return Err(Failure::Usage(format!("--{} needs a value", name))),
+            },
+        };
+        if !["origin", "base", "length", "count", "format", "volume"].contains(&name.as_str()) {
+            return Err(Failure::Usage(format!("unknown option --{}", name)));
+        }
+        options.insert(name, value);
+    }
+
+    Ok(Args {
+        command,
+        positional,
+        options,
+        json,
+    })
+}
+
+fn run(args: &Args) -> Result<String, Failure> {
+    match args.command.as_str() {
+        "generate" => generate(args),
+        "decode" => decode(args),
+        "encode" => encode(args),
+        "bounds" => bounds(args),
+        command => Err(Failure::Usage(format!("unknown command {}", command))),
+    }
+}
+
+fn generate(args: &Args) -> Result<String, Failure> {
+    expect_positional(args, 0)?;
+    let defaults = Settings::default();
+    let settings = Settings {
+        origin_system_time: get_origin(args)?,
+        logical_volume_base: get_number(args, "base", defaults.logical_volume_base)?,
+        logical_volume_length: get_number(args, "length", defaults.logical_volume_length)?,
+        exhaustion_policy: ExhaustionPolicy::Sleep,
+        ..defaults
+    };
+    let count = get_number(args, "count", 1)?;
+    if u64::from(u32::MAX) < count {
+        return Err(Failure::Usage(format!(
+            "--count must be at most {}, found {}",
+            u32::MAX,
+            count
+        )));
+    }
+    let encoding = get_encoding(args, Encoding::Decimal)?;
+
+    let mut snowprinter = Snowprint::new(settings).map_err(runtime)?;
+    let snowprints = snowprinter.compose_batch(count as usize).map_err(runtime)?;
+
+    let output: Vec<String> = snowprints
+        .iter()
+        .map(|snowprint| match (args.json, encoding) {
+            (true, Encoding::Decimal) => snowprint.as_u64().to_string(),
+            (true, _) => format!("\"{}\"", snowprint.encode(encoding)),
+            _ => format_id(*snowprint, encoding),
+        })
+        .collect();
+    match args.json {
+        true => Ok(format!("[{}]", output.join(","))),
+        _ => Ok(output.join("\n")),
+    }
+}
+
+fn decode(args: &Args) -> Result<String, Failure> {
+    expect_positional(args, 1)?;
+    let origin_system_time = get_origin(args)?;
+    let encoding = get_encoding(args, Encoding::Decimal)?;
+    let snowprint = parse_id(&args.positional[0], encoding)?;
+
+    let created_at = rfc3339::format(snowprint.to_system_time(origin_system_time));
+    let fields = [
+        ("id", snowprint.as_u64().to_string(), false),
+        ("created_at", created_at, true),
+        ("timestamp_ms", snowprint.timestamp_ms().to_string(), false),
+        (
+            "logical_volume",
+            snowprint.logical_volume().to_string(),
+            false,
+        ),
+        ("sequence", snowprint.sequence().to_string(), false),
+        ("base32", snowprint.encode(Encoding::CrockfordBase32), true),
+        ("base62", snowprint.encode(Encoding::Base62), true),
+    ];
+
+    match args.json {
+        true => Ok(format_json_object(&fields)),
+        _ => Ok(fields
+            .iter()
+            .map(|(name, value, _)| format!("{}: {}", name, value))
+            .collect::<Vec<String>>()
+            .join("\n")),
+    }
+}
+
+fn encode(args: &Args) -> Result<String, Failure> {
+    expect_positional(args, 1)?;
+    let encoding = get_encoding(args, Encoding::CrockfordBase32)?;
+    let snowprint = parse_id(&args.positional[0], Encoding::Decimal)?;
+
+    match args.json {
+        true => Ok(format_json_object(&[
+            ("id", snowprint.as_u64().to_string(), false),
+            ("encoded", snowprint.encode(encoding), true),
+        ])),
+        _ => Ok(format_id(snowprint, encoding)),
+    }
+}
+
+fn bounds(args: &Args) -> Result<String, Failure> {
+    expect_positional(args, 2)?;
+    let origin_system_time = get_origin(args)?;
+    let from = parse_time(&args.positional[0])?;
+    let to = parse_time(&args.positional[1])?;
+
+    let range = match args.options.get("volume") {
+        Some(_) => {
+            let logical_volume = get_number(args, "volume", 0)?;
+            range_for_logical_volume(from..to, origin_system_time, logical_volume)
+                .map_err(runtime)?
+        }
+        _ => range_for(from..to, origin_system_time),
+    };
+    if range.is_empty() {
+        return Err(Failure::Runtime(
+            "no snowprints can be composed in an empty window".to_string(),
+        ));
+    }
+
+    match args.json {
+        true => Ok(format_json_object(&[
+            ("lower_bound", range.start().to_string(), false),
+            ("upper_bound", range.end().to_string(), false),
+        ])),
+        _ => Ok(format!(
+            "lower_bound: {}\nupper_bound: {}",
+            range.start(),
+            range.end()
+        )),
+    }
+}
+
+fn expect_positional(args: &Args, length: usize) -> Result<(), Failure> {
+    match args.positional.len() == length {
+        true => Ok(()),
+        _ => Err(Failure::Usage(format!(
+            "{} expects {} argument(s), found {}",
+            args.command,
+            length,
+            args.positional.len()
+        ))),
+    }
+}
+
+fn get_origin(args: &Args) -> Result<SystemTime, Failure> {
+    match args.options.get("origin") {
+        Some(origin) => parse_time(origin),
+        _ => Ok(UNIX_EPOCH),
+    }
+}
+
+fn get_number(args: &Args, name: &str, default: u64) -> Result<u64, Failure> {
+    match args.options.get(name) {
+        Some(value) => value
+            .parse()
+            .map_err(|_| Failure::Usage(format!("--{} expects a number, found {}", name, value))),
+        _ => Ok(default),
+    }
+}
+
+fn get_encoding(args: &Args, default: Encoding) -> Result<Encoding, Failure> {
+    match args.options.get("format").map(String::as_str) {
+        Some("decimal") => Ok(Encoding::Decimal),
+        Some("base32") | Some("crockford-base32") => Ok(Encoding::CrockfordBase32),
+        Some("base62") => Ok(Encoding::Base62),
+        Some(format) => Err(Failure::Usage(format!("unknown format {}", format))),
+        _ => Ok(default),
+    }
+}
+
+// decimal ids are usually copied out of a database without padding
+fn parse_id(id: &str, encoding: Encoding) -> Result<SnowprintId, Failure> {
+    match encoding {
+        Encoding::Decimal => id
+            .parse::<u64>()
+            .map(SnowprintId::from)
+            .map_err(|_| Failure::Runtime(format!("{} is not a decimal snowprint", id))),
+        encoding => SnowprintId::decode(id, encoding).map_err(runtime),
+    }
+}
+
+fn parse_time(time: &str) -> Result<SystemTime, Failure> {
+    if let Ok(epoch_ms) = time.parse::<u64>() {
+        return Ok(UNIX_EPOCH + Duration::from_millis(epoch_ms));
+    }
+    rfc3339::parse(time).ok_or_else(|| {
+        Failure::Usage(format!(
+            "{} is not an RFC 3339 timestamp or ms since the unix epoch",
+            time
+        ))
+    })
+}
+
+// decimal stays unpadded on the command line so it matches the database
+fn format_id(snowprint: SnowprintId, encoding: Encoding) -> String {
+    match encoding {
+        Encoding::Decimal => snowprint.as_u64().to_string(),
+        encoding => snowprint.encode(encoding),
+    }
+}
+
+// values never need escaping, they are numbers, timestamps, or encoded ids
+fn format_json_object(fields: &[(&str, String, bool)]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(name, value, is_string)| match is_string {
+            true => format!("\"{}\":\"{}\"", name, value),
+            _ => format!("\"{}\":{}", name, value),
+        })
+        .collect();
+    format!("{{{}}}", fields.join(","))
+}
+
+fn runtime(err: snowprints::Error) -> Failure {
+    Failure::Runtime(err.to_string())
+}
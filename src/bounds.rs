@@ -0,0 +1,62 @@
+// Smallest and largest snowprints for a window of time
+//     - bounds are inclusive, so they fit BETWEEN in sql
+//     - times are floored to the ms, the window's end is exclusive
+//     - times before origin_system_time or past MAX_TIMESTAMP are clamped
+
+use crate::{compose, try_compose, Error, MAX_LOGICAL_VOLUMES, MAX_SEQUENCES, MAX_TIMESTAMP};
+use std::ops::{Range, RangeInclusive};
+use std::time::SystemTime;
+
+pub fn lower_bound(system_time: SystemTime, origin_system_time: SystemTime) -> u64 {
+    compose(get_duration_ms(system_time, origin_system_time), 0, 0)
+}
+
+pub fn upper_bound(system_time: SystemTime, origin_system_time: SystemTime) -> u64 {
+    compose(
+        get_duration_ms(system_time, origin_system_time),
+        MAX_LOGICAL_VOLUMES - 1,
+        MAX_SEQUENCES - 1,
+    )
+}
+
+// an empty window returns an empty range
+pub fn range_for(
+    system_times: Range<SystemTime>,
+    origin_system_time: SystemTime,
+) -> RangeInclusive<u64> {
+    let start_ms = get_duration_ms(system_times.start, origin_system_time);
+    let end_ms = get_duration_ms(system_times.end, origin_system_time);
+    match start_ms < end_ms {
+        true => {
+            compose(start_ms, 0, 0)
+                ..=compose(end_ms - 1, MAX_LOGICAL_VOLUMES - 1, MAX_SEQUENCES - 1)
+        }
+        _ => empty_range(),
+    }
+}
+
+// still contains other logical volumes, but skips most of the window that can't match
+pub fn range_for_logical_volume(
+    system_times: Range<SystemTime>,
+    origin_system_time: SystemTime,
+    logical_volume: u64,
+) -> Result<RangeInclusive<u64>, Error> {
+    let start_ms = get_duration_ms(system_times.start, origin_system_time);
+    let end_ms = get_duration_ms(system_times.end, origin_system_time);
+    let start = try_compose(start_ms, logical_volume, 0)?;
+    match start_ms < end_ms {
+        true => Ok(start..=try_compose(end_ms - 1, logical_volume, MAX_SEQUENCES - 1)?),
+        _ => Ok(empty_range()),
+    }
+}
+
+fn get_duration_ms(system_time: SystemTime, origin_system_time: SystemTime) -> u64 {
+    match system_time.duration_since(origin_system_time) {
+        Ok(duration) => duration.as_millis().min(MAX_TIMESTAMP as u128) as u64,
+        _ => 0,
+    }
+}
+
+fn empty_range() -> RangeInclusive<u64> {
+    RangeInclusive::new(1, 0)
+}
@@ -0,0 +1,122 @@
+// Sources of time for snowprint generation
+//     - SystemClock reads the wall clock, same as SystemTime::now()
+//     - MonotonicClock reads the wall clock once then steps forward with Instant
+//     - ManualClock only moves when told to, for tests and simulations
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+pub trait Clock {
+    fn now(&self) -> SystemTime;
+}
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+// immune to wall clock adjustments after creation, but drifts from NTP over time
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct MonotonicClock {
+    anchor_system_time: SystemTime,
+    anchor_instant: Instant,
+}
+
+impl MonotonicClock {
+    pub fn new() -> MonotonicClock {
+        MonotonicClock {
+            anchor_system_time: SystemTime::now(),
+            anchor_instant: Instant::now(),
+        }
+    }
+}
+
+impl Default for MonotonicClock {
+    fn default() -> MonotonicClock {
+        MonotonicClock::new()
+    }
+}
+
+impl Clock for MonotonicClock {
+    fn now(&self) -> SystemTime {
+        self.anchor_system_time + self.anchor_instant.elapsed()
+    }
+}
+
+// clones share the same time, so a test can keep a handle to a generator's clock
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    now: Arc<Mutex<SystemTime>>,
+}
+
+impl ManualClock {
+    pub fn new(now: SystemTime) -> ManualClock {
+        ManualClock {
+            now: Arc::new(Mutex::new(now)),
+        }
+    }
+
+    pub fn set(&self, now: SystemTime) {
+        *self.lock() = now;
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        *self.lock() += duration;
+    }
+
+    pub fn rewind(&self, duration: Duration) {
+        *self.lock() -= duration;
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, SystemTime> {
+        // a SystemTime cannot be left half written, so a poisoned lock is still valid
+        match self.now.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        }
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> SystemTime {
+        *self.lock()
+    }
+}
+
+// called with how far the clock moved backwards, every time it is noticed
+#[derive(Clone)]
+pub struct ClockSkewHook {
+    hook: Arc<dyn Fn(u64) + Send + Sync>,
+}
+
+impl ClockSkewHook {
+    pub fn new(hook: impl Fn(u64) + Send + Sync + 'static) -> ClockSkewHook {
+        ClockSkewHook {
+            hook: Arc::new(hook),
+        }
+    }
+
+    pub fn call(&self, by_ms: u64) {
+        (self.hook)(by_ms)
+    }
+}
+
+impl fmt::Debug for ClockSkewHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ClockSkewHook")
+    }
+}
+
+// hooks are equal when they are clones of each other
+impl PartialEq for ClockSkewHook {
+    fn eq(&self, other: &ClockSkewHook) -> bool {
+        Arc::ptr_eq(&self.hook, &other.hook)
+    }
+}
+
+impl Eq for ClockSkewHook {}
@@ -0,0 +1,122 @@
+// Fixed width string encodings for snowprints
+//     - every encoding pads to a fixed width so string order matches numeric order
+//     - alphabets are in ascii order for the same reason
+//     - Crockford base32 decodes case-insensitively and reads I, L as 1 and O as 0
+
+use crate::Error;
+use std::fmt;
+
+const CROCKFORD_BASE32_ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+const BASE62_ALPHABET: &[u8; 62] =
+    b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+// ceil(64 / log2(radix)) digits fit every u64
+const CROCKFORD_BASE32_WIDTH: usize = 13;
+const BASE62_WIDTH: usize = 11;
+const DECIMAL_WIDTH: usize = 20;
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+pub enum Encoding {
+    #[default]
+    CrockfordBase32,
+    Base62,
+    Decimal,
+}
+
+impl Encoding {
+    pub fn width(&self) -> usize {
+        match self {
+            Encoding::CrockfordBase32 => CROCKFORD_BASE32_WIDTH,
+            Encoding::Base62 => BASE62_WIDTH,
+            Encoding::Decimal => DECIMAL_WIDTH,
+        }
+    }
+
+    pub fn encode(&self, snowprint: u64) -> String {
+        let radix = self.radix();
+        let mut digits = vec![b'0'; self.width()];
+        let mut remainder = snowprint;
+        for digit in digits.iter_mut().rev() {
+            *digit = self.alphabet()[(remainder % radix) as usize];
+            remainder /= radix;
+        }
+
+        // every alphabet is ascii
+        String::from_utf8(digits).unwrap_or_default()
+    }
+
+    pub fn decode(&self, encoded: &str) -> Result<u64, Error> {
+        let length = encoded.chars().count();
+        if length != self.width() {
+            return Err(Error::InvalidEncodedLength {
+                encoding: *self,
+                length,
+            });
+        }
+
+        let radix = self.radix();
+        let mut snowprint: u64 = 0;
+        for character in encoded.chars() {
+            let digit = match self.decode_digit(character) {
+                Some(digit) => digit,
+                _ => {
+                    return Err(Error::InvalidEncodedCharacter {
+                        encoding: *self,
+                        character,
+                    })
+                }
+            };
+            snowprint = match snowprint
+                .checked_mul(radix)
+                .and_then(|snowprint| snowprint.checked_add(digit))
+            {
+                Some(snowprint) => snowprint,
+                _ => return Err(Error::ExceededEncodedRange { encoding: *self }),
+            };
+        }
+
+        Ok(snowprint)
+    }
+
+    fn radix(&self) -> u64 {
+        self.alphabet().len() as u64
+    }
+
+    fn alphabet(&self) -> &'static [u8] {
+        match self {
+            Encoding::CrockfordBase32 => CROCKFORD_BASE32_ALPHABET,
+            Encoding::Base62 => BASE62_ALPHABET,
+            Encoding::Decimal => &BASE62_ALPHABET[..10],
+        }
+    }
+
+    fn decode_digit(&self, character: char) -> Option<u64> {
+        let character = match self {
+            Encoding::CrockfordBase32 => match character.to_ascii_uppercase() {
+                'I' | 'L' => '1',
+                'O' => '0',
+                character => character,
+            },
+            _ => character,
+        };
+
+        match character.is_ascii() {
+            true => self
+                .alphabet()
+                .iter()
+                .position(|&digit| digit == character as u8)
+                .map(|position| position as u64),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Encoding::CrockfordBase32 => f.write_str("crockford base32"),
+            Encoding::Base62 => f.write_str("base62"),
+            Encoding::Decimal => f.write_str("decimal"),
+        }
+    }
+}
@@ -0,0 +1,56 @@
+// What to do when every logical volume and sequence of a ms has been used
+//     - ReturnError returns Error::ExceededAvailableSequences (default)
+//     - Spin busy-waits until the clock reaches the next ms
+//     - Sleep parks the thread until the next ms
+
+use crate::Clock;
+use std::hint;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ExhaustionPolicy {
+    #[default]
+    ReturnError,
+    Spin,
+    Sleep,
+}
+
+// returns once the clock has passed duration_ms, or after a single sleep
+// so callers re-read the clock and retry
+pub(crate) fn wait_for_next_ms(
+    clock: &impl Clock,
+    origin_system_time: SystemTime,
+    duration_ms: u64,
+    exhaustion_policy: ExhaustionPolicy,
+) {
+    match exhaustion_policy {
+        ExhaustionPolicy::ReturnError => {}
+        ExhaustionPolicy::Spin => {
+            let next_ms = origin_system_time + Duration::from_millis(duration_ms + 1);
+            while clock.now() < next_ms {
+                hint::spin_loop();
+            }
+        }
+        ExhaustionPolicy::Sleep => {
+            if let Some(nap) = get_nap_until_next_ms(clock, origin_system_time, duration_ms) {
+                thread::sleep(nap);
+            }
+        }
+    }
+}
+
+// nap at most 1ms so a clock that jumps forward is noticed
+pub(crate) fn get_nap_until_next_ms(
+    clock: &impl Clock,
+    origin_system_time: SystemTime,
+    duration_ms: u64,
+) -> Option<Duration> {
+    let next_ms = origin_system_time + Duration::from_millis(duration_ms + 1);
+    match next_ms.duration_since(clock.now()) {
+        Ok(remaining) => Some(remaining.min(Duration::from_millis(1))),
+        _ => None,
+    }
+}
@@ -0,0 +1,373 @@
+// A gRPC service that owns one generator, behind the `grpc` feature
+//     - Next, NextBatch, and Decode are unary calls over proto/snowprints.proto
+//     - Lease hands a client a contiguous run of logical volumes for a time window
+//     - leased volumes come from their own pool so they never overlap the server's settings
+//     - a lease is renewed while its stream stays open and lapses once it closes
+//     - leases kept in memory are lost on restart, so a new server waits out the old grants
+
+use crate::lease::get_unix_ms;
+use crate::{
+    get_logical_volume_index, rfc3339, AsyncSnowprint, Error, FileLeaseStore, MemoryLeaseStore,
+    Settings, SnowprintId, VolumeLease, VolumeLeaseStore, MAX_LOGICAL_VOLUMES,
+};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, watch};
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+use tonic::{Request, Response, Status};
+
+pub mod proto {
+    tonic::include_proto!("snowprints.v1");
+}
+
+use proto::snowprints_server::{Snowprints, SnowprintsServer};
+use proto::{
+    DecodeRequest, DecodeResponse, LeaseRequest, LeaseResponse, NextBatchRequest,
+    NextBatchResponse, NextRequest, NextResponse,
+};
+
+const MIN_LEASE_MS: u64 = 10;
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GrpcConfig {
+    pub address: String,
+    pub max_count: usize,
+    pub max_lease_ms: u64,
+    pub lease_logical_volume_base: u64,
+    pub lease_logical_volume_length: u64,
+    pub lease_store_path: Option<PathBuf>,
+    pub settings: Settings,
+}
+
+// the server rotates the lower half of the logical volumes and leases the upper half
+impl Default for GrpcConfig {
+    fn default() -> GrpcConfig {
+        let half = MAX_LOGICAL_VOLUMES / 2;
+        GrpcConfig {
+            address: "127.0.0.1:7879".to_string(),
+            max_count: 1000,
+            max_lease_ms: 60_000,
+            lease_logical_volume_base: half,
+            lease_logical_volume_length: half,
+            lease_store_path: None,
+            settings: Settings {
+                logical_volume_base: 0,
+                logical_volume_length: half,
+                ..Settings::default()
+            },
+        }
+    }
+}
+
+impl GrpcConfig {
+    pub fn from_file(path: impl AsRef<Path>) -> io::Result<GrpcConfig> {
+        let contents = fs::read_to_string(path)?;
+        serde_json::from_str(&contents)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
+
+#[derive(Debug)]
+pub struct GrpcServer {
+    listener: TcpListener,
+    service: SnowprintsService,
+}
+
+impl GrpcServer {
+    pub async fn bind(config: GrpcConfig) -> io::Result<GrpcServer> {
+        let address = config.address.clone();
+        let service = SnowprintsService::new(config).map_err(into_io_error)?;
+        let listener = TcpListener::bind(&address).await?;
+
+        Ok(GrpcServer { listener, service })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    // serves until shutdown resolves, open lease streams end so the server can drain
+    pub async fn run(self, shutdown: impl Future<Output = ()>) -> io::Result<()> {
+        let closing = self.service.closing.clone();
+        let shutdown = async move {
+            shutdown.await;
+            let _ = closing.send(true);
+        };
+
+        tonic::transport::Server::builder()
+            .add_service(SnowprintsServer::new(self.service))
+            .serve_with_incoming_shutdown(TcpListenerStream::new(self.listener), shutdown)
+            .await
+            .map_err(io::Error::other)
+    }
+}
+
+#[derive(Debug)]
+pub struct SnowprintsService {
+    snowprinter: AsyncSnowprint,
+    origin_system_time: SystemTime,
+    max_count: usize,
+    max_lease_ms: u64,
+    leases: LeaseStore,
+    leases_open_at_ms: u64,
+    lease_logical_volume_length: u64,
+    lease_count: AtomicU64,
+    closing: watch::Sender<bool>,
+}
+
+impl SnowprintsService {
+    pub fn new(config: GrpcConfig) -> Result<SnowprintsService, Error> {
+        check_lease_pool(&config)?;
+        let origin_system_time = config.settings.origin_system_time;
+        let (base, length) = (
+            config.lease_logical_volume_base,
+            config.lease_logical_volume_length,
+        );
+        let (leases, leases_open_at_ms) = match config.lease_store_path {
+            Some(path) => (LeaseStore::File(FileLeaseStore::new(path, base, length)), 0),
+            // grants from before a restart last at most max_lease_ms past it
+            None => (
+                LeaseStore::Memory(MemoryLeaseStore::new(base, length)),
+                now_ms().saturating_add(config.max_lease_ms),
+            ),
+        };
+
+        Ok(SnowprintsService {
+            snowprinter: AsyncSnowprint::new(config.settings)?,
+            origin_system_time,
+            max_count: config.max_count,
+            max_lease_ms: config.max_lease_ms,
+            leases,
+            leases_open_at_ms,
+            lease_logical_volume_length: config.lease_logical_volume_length,
+            lease_count: AtomicU64::new(0),
+            closing: watch::channel(false).0,
+        })
+    }
+}
+
+#[tonic::async_trait]
+impl Snowprints for SnowprintsService {
+    async fn next(&self, _request: Request<NextRequest>) -> Result<Response<NextResponse>, Status> {
+        let snowprint = self.snowprinter.compose().await.map_err(into_status)?;
+        Ok(Response::new(NextResponse {
+            id: snowprint.into(),
+        }))
+    }
+
+    async fn next_batch(
+        &self,
+        request: Request<NextBatchRequest>,
+    ) -> Result<Response<NextBatchResponse>, Status> {
+        let count = request.into_inner().count as usize;
+        if count == 0 || self.max_count < count {
+            return Err(Status::invalid_argument(format!(
+                "count must be between 1 and {}",
+                self.max_count
+            )));
+        }
+
+        let mut ids = Vec::with_capacity(count);
+        for _ in 0..count {
+            let snowprint = self.snowprinter.compose().await.map_err(into_status)?;
+            ids.push(snowprint.into());
+        }
+        Ok(Response::new(NextBatchResponse { ids }))
+    }
+
+    async fn decode(
+        &self,
+        request: Request<DecodeRequest>,
+    ) -> Result<Response<DecodeResponse>, Status> {
+        let snowprint = SnowprintId::from(request.into_inner().id);
+        Ok(Response::new(DecodeResponse {
+            id: snowprint.into(),
+            created_at: rfc3339::format(snowprint.to_system_time(self.origin_system_time)),
+            timestamp_ms: snowprint.timestamp_ms(),
+            logical_volume: snowprint.logical_volume(),
+            sequence: snowprint.sequence(),
+        }))
+    }
+
+    type LeaseStream = ReceiverStream<Result<LeaseResponse, Status>>;
+
+    async fn lease(
+        &self,
+        request: Request<LeaseRequest>,
+    ) -> Result<Response<Self::LeaseStream>, Status> {
+        let request = request.into_inner();
+        if request.duration_ms < MIN_LEASE_MS || self.max_lease_ms < request.duration_ms {
+            return Err(Status::invalid_argument(format!(
+                "duration_ms must be between {} and {}",
+                MIN_LEASE_MS, self.max_lease_ms
+            )));
+        }
+        if request.logical_volume_length == 0
+            || self.lease_logical_volume_length < request.logical_volume_length
+        {
+            return Err(Status::invalid_argument(format!(
+                "logical_volume_length must be between 1 and {}",
+                self.lease_logical_volume_length
+            )));
+        }
+
+        let now = now_ms();
+        if now < self.leases_open_at_ms {
+            return Err(Status::unavailable(format!(
+                "leases open in {} ms",
+                self.leases_open_at_ms - now
+            )));
+        }
+
+        // every stream holds its lease under its own name
+        let holder = format!("lease-{}", self.lease_count.fetch_add(1, Ordering::Relaxed));
+        let mut leases = self.leases.clone();
+        let mut lease = leases
+            .acquire(
+                &holder,
+                request.logical_volume_length,
+                request.duration_ms,
+                now,
+            )
+            .map_err(|err| match err {
+                Error::NoLogicalVolumesToLease { .. } => {
+                    Status::resource_exhausted(err.to_string())
+                }
+                err => into_status(err),
+            })?;
+
+        let (sender, receiver) = mpsc::channel(1);
+        let origin_unix_ms = get_unix_ms(self.origin_system_time);
+        let renew_after = Duration::from_millis(request.duration_ms / 2);
+        let mut closing = self.closing.subscribe();
+
+        tokio::spawn(async move {
+            loop {
+                let response = LeaseResponse {
+                    logical_volume_base: lease.logical_volume_base,
+                    logical_volume_length: lease.logical_volume_length,
+                    expires_at_unix_ms: lease.expires_at_ms,
+                    origin_unix_ms,
+                };
+                if sender.send(Ok(response)).await.is_err() {
+                    break;
+                }
+
+                tokio::select! {
+                    _ = tokio::time::sleep(renew_after) => {}
+                    _ = sender.closed() => break,
+                    _ = closing.wait_for(|is_closing| *is_closing) => break,
+                }
+
+                match leases.heartbeat(&lease, request.duration_ms, now_ms()) {
+                    Ok(renewed) => lease = renewed,
+                    _ => {
+                        let _ = sender.send(Err(Status::aborted("lease lapsed"))).await;
+                        break;
+                    }
+                }
+            }
+            // the volumes stay reserved until the last grant expires,
+            // the client may still be composing on them until then
+        });
+
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+}
+
+// leases only outlive a restart when they're kept in a file
+#[derive(Debug, Clone)]
+enum LeaseStore {
+    Memory(MemoryLeaseStore),
+    File(FileLeaseStore),
+}
+
+impl VolumeLeaseStore for LeaseStore {
+    fn acquire(
+        &mut self,
+        holder: &str,
+        logical_volume_length: u64,
+        ttl_ms: u64,
+        now_ms: u64,
+    ) -> Result<VolumeLease, Error> {
+        match self {
+            LeaseStore::Memory(leases) => {
+                leases.acquire(holder, logical_volume_length, ttl_ms, now_ms)
+            }
+            LeaseStore::File(leases) => {
+                leases.acquire(holder, logical_volume_length, ttl_ms, now_ms)
+            }
+        }
+    }
+
+    fn heartbeat(
+        &mut self,
+        lease: &VolumeLease,
+        ttl_ms: u64,
+        now_ms: u64,
+    ) -> Result<VolumeLease, Error> {
+        match self {
+            LeaseStore::Memory(leases) => leases.heartbeat(lease, ttl_ms, now_ms),
+            LeaseStore::File(leases) => leases.heartbeat(lease, ttl_ms, now_ms),
+        }
+    }
+
+    fn release(&mut self, lease: &VolumeLease) -> Result<(), Error> {
+        match self {
+            LeaseStore::Memory(leases) => leases.release(lease),
+            LeaseStore::File(leases) => leases.release(lease),
+        }
+    }
+}
+
+fn check_lease_pool(config: &GrpcConfig) -> Result<(), Error> {
+    if config.lease_logical_volume_length == 0 {
+        return Err(Error::LogicalVolumeModuloIsZero);
+    }
+    // the pool comes from a config file, so it may be large enough to overflow
+    let lease_end = match config
+        .lease_logical_volume_base
+        .checked_add(config.lease_logical_volume_length)
+    {
+        Some(lease_end) if lease_end <= MAX_LOGICAL_VOLUMES => lease_end,
+        _ => {
+            return Err(Error::ExceededAvailableLogicalVolumes {
+                logical_volume_base: config.lease_logical_volume_base,
+                logical_volume_length: config.lease_logical_volume_length,
+            })
+        }
+    };
+    for logical_volume in config.lease_logical_volume_base..lease_end {
+        if get_logical_volume_index(&config.settings, logical_volume).is_some() {
+            return Err(Error::LeasedLogicalVolumeInSettings { logical_volume });
+        }
+    }
+
+    Ok(())
+}
+
+// clock trouble passes with time, everything else is the server's fault
+fn into_status(err: Error) -> Status {
+    match err {
+        Error::ExceededAvailableSequences
+        | Error::ClockMovedBackwards { .. }
+        | Error::ClockBehindHighWaterMark { .. } => Status::unavailable(err.to_string()),
+        _ => Status::internal(err.to_string()),
+    }
+}
+
+fn into_io_error(err: Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, err)
+}
+
+fn now_ms() -> u64 {
+    get_unix_ms(SystemTime::now())
+}
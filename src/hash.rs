@@ -0,0 +1,11 @@
+// Small non-cryptographic mixing shared across modules
+//     - murmur3's 64 bit finalizer, every output bit depends on every input bit
+
+pub(crate) fn mix(value: u64) -> u64 {
+    let mut value = value;
+    value ^= value >> 33;
+    value = value.wrapping_mul(0xff51afd7ed558ccd);
+    value ^= value >> 33;
+    value = value.wrapping_mul(0xc4ceb9fe1a85ec53);
+    value ^ value >> 33
+}
@@ -0,0 +1,72 @@
+// A typed snowprint so fields can't be mixed up
+//     - ordering matches the underlying u64, which is sorted by time first
+//     - raw compose and decompose remain for u64 based code
+//     - Display and FromStr use Crockford base32
+
+use crate::{decompose, Encoding, Error};
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct SnowprintId(u64);
+
+impl SnowprintId {
+    pub fn timestamp_ms(&self) -> u64 {
+        let (timestamp_ms, _, _) = decompose(self.0);
+        timestamp_ms
+    }
+
+    pub fn logical_volume(&self) -> u64 {
+        let (_, logical_volume, _) = decompose(self.0);
+        logical_volume
+    }
+
+    pub fn sequence(&self) -> u64 {
+        let (_, _, sequence) = decompose(self.0);
+        sequence
+    }
+
+    // origin must be the same origin_system_time used to compose the snowprint
+    pub fn to_system_time(&self, origin_system_time: SystemTime) -> SystemTime {
+        origin_system_time + Duration::from_millis(self.timestamp_ms())
+    }
+
+    pub fn as_u64(&self) -> u64 {
+        self.0
+    }
+
+    pub fn encode(&self, encoding: Encoding) -> String {
+        encoding.encode(self.0)
+    }
+
+    pub fn decode(encoded: &str, encoding: Encoding) -> Result<SnowprintId, Error> {
+        encoding.decode(encoded).map(SnowprintId)
+    }
+}
+
+impl fmt::Display for SnowprintId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.encode(Encoding::CrockfordBase32))
+    }
+}
+
+impl FromStr for SnowprintId {
+    type Err = Error;
+
+    fn from_str(encoded: &str) -> Result<SnowprintId, Error> {
+        SnowprintId::decode(encoded, Encoding::CrockfordBase32)
+    }
+}
+
+impl From<u64> for SnowprintId {
+    fn from(snowprint: u64) -> SnowprintId {
+        SnowprintId(snowprint)
+    }
+}
+
+impl From<SnowprintId> for u64 {
+    fn from(snowprint_id: SnowprintId) -> u64 {
+        snowprint_id.0
+    }
+}
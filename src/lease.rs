@@ -0,0 +1,518 @@
+// Lease logical volumes so generators sharing a store never overlap
+//     - a lease is a contiguous run of logical volumes held until expires_at_ms
+//     - holders heartbeat to push expires_at_ms forward, expired leases are free to take
+//     - releasing pulls expires_at_ms in, a held ms is never handed to the next holder
+//     - LeasedSnowprint acquires its volumes on creation and stops once its lease lapses
+
+use crate::{Clock, Error, Settings, Snowprint, SnowprintId, SystemClock, MAX_LOGICAL_VOLUMES};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// times are ms since the unix epoch so holders with different origins agree
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VolumeLease {
+    pub holder: String,
+    pub logical_volume_base: u64,
+    pub logical_volume_length: u64,
+    pub expires_at_ms: u64,
+}
+
+pub trait VolumeLeaseStore {
+    fn acquire(
+        &mut self,
+        holder: &str,
+        logical_volume_length: u64,
+        ttl_ms: u64,
+        now_ms: u64,
+    ) -> Result<VolumeLease, Error>;
+    fn heartbeat(
+        &mut self,
+        lease: &VolumeLease,
+        ttl_ms: u64,
+        now_ms: u64,
+    ) -> Result<VolumeLease, Error>;
+    // holds the volumes until lease.expires_at_ms, or the current expiry if that's sooner
+    fn release(&mut self, lease: &VolumeLease) -> Result<(), Error>;
+}
+
+// clones share the same leases, for generators within one process
+#[derive(Debug, Clone)]
+pub struct MemoryLeaseStore {
+    logical_volume_base: u64,
+    logical_volume_length: u64,
+    leases: Arc<Mutex<Vec<VolumeLease>>>,
+}
+
+impl MemoryLeaseStore {
+    pub fn new(logical_volume_base: u64, logical_volume_length: u64) -> MemoryLeaseStore {
+        MemoryLeaseStore {
+            logical_volume_base,
+            logical_volume_length,
+            leases: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<VolumeLease>> {
+        self.leases.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl VolumeLeaseStore for MemoryLeaseStore {
+    fn acquire(
+        &mut self,
+        holder: &str,
+        logical_volume_length: u64,
+        ttl_ms: u64,
+        now_ms: u64,
+    ) -> Result<VolumeLease, Error> {
+        let (pool_base, pool_length) = (self.logical_volume_base, self.logical_volume_length);
+        acquire_from_leases(
+            &mut self.lock(),
+            pool_base,
+            pool_length,
+            holder,
+            logical_volume_length,
+            ttl_ms,
+            now_ms,
+        )
+    }
+
+    fn heartbeat(
+        &mut self,
+        lease: &VolumeLease,
+        ttl_ms: u64,
+        now_ms: u64,
+    ) -> Result<VolumeLease, Error> {
+        heartbeat_in_leases(&mut self.lock(), lease, ttl_ms, now_ms)
+    }
+
+    fn release(&mut self, lease: &VolumeLease) -> Result<(), Error> {
+        release_from_leases(&mut self.lock(), lease);
+        Ok(())
+    }
+}
+
+// every change locks the whole file, for generators sharing a host or a network mount
+//     - one lease per line: base length expires_at_ms holder
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FileLeaseStore {
+    path: PathBuf,
+    logical_volume_base: u64,
+    logical_volume_length: u64,
+}
+
+impl FileLeaseStore {
+    pub fn new(
+        path: impl Into<PathBuf>,
+        logical_volume_base: u64,
+        logical_volume_length: u64,
+    ) -> FileLeaseStore {
+        FileLeaseStore {
+            path: path.into(),
+            logical_volume_base,
+            logical_volume_length,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // the lock is held from reading the leases until the changes are synced
+    fn update<T>(
+        &self,
+        change: impl FnOnce(&mut Vec<VolumeLease>) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&self.path)
+            .map_err(into_lease_store_error)?;
+        file.lock().map_err(into_lease_store_error)?;
+
+        let mut leases = read_leases(&mut file)?;
+        let changed = change(&mut leases)?;
+        write_leases(&mut file, &leases)?;
+
+        Ok(changed)
+    }
+}
+
+impl VolumeLeaseStore for FileLeaseStore {
+    fn acquire(
+        &mut self,
+        holder: &str,
+        logical_volume_length: u64,
+        ttl_ms: u64,
+        now_ms: u64,
+    ) -> Result<VolumeLease, Error> {
+        self.update(|leases| {
+            acquire_from_leases(
+                leases,
+                self.logical_volume_base,
+                self.logical_volume_length,
+                holder,
+                logical_volume_length,
+                ttl_ms,
+                now_ms,
+            )
+        })
+    }
+
+    fn heartbeat(
+        &mut self,
+        lease: &VolumeLease,
+        ttl_ms: u64,
+        now_ms: u64,
+    ) -> Result<VolumeLease, Error> {
+        self.update(|leases| heartbeat_in_leases(leases, lease, ttl_ms, now_ms))
+    }
+
+    fn release(&mut self, lease: &VolumeLease) -> Result<(), Error> {
+        self.update(|leases| {
+            release_from_leases(leases, lease);
+            Ok(())
+        })
+    }
+}
+
+fn read_leases(file: &mut File) -> Result<Vec<VolumeLease>, Error> {
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)
+        .map_err(into_lease_store_error)?;
+
+    let mut leases = Vec::new();
+    for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+        let mut parts = line.splitn(4, ' ');
+        let lease = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(base), Some(length), Some(expires_at_ms), Some(holder)) => {
+                match (base.parse(), length.parse(), expires_at_ms.parse()) {
+                    (Ok(logical_volume_base), Ok(logical_volume_length), Ok(expires_at_ms)) => {
+                        Some(VolumeLease {
+                            holder: holder.to_string(),
+                            logical_volume_base,
+                            logical_volume_length,
+                            expires_at_ms,
+                        })
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        match lease {
+            Some(lease) => leases.push(lease),
+            _ => {
+                return Err(Error::FailedToAccessLeaseStore {
+                    kind: io::ErrorKind::InvalidData,
+                })
+            }
+        }
+    }
+
+    Ok(leases)
+}
+
+fn write_leases(file: &mut File, leases: &[VolumeLease]) -> Result<(), Error> {
+    let mut contents = String::new();
+    for lease in leases {
+        contents.push_str(&format!(
+            "{} {} {} {}\n",
+            lease.logical_volume_base,
+            lease.logical_volume_length,
+            lease.expires_at_ms,
+            lease.holder
+        ));
+    }
+
+    file.set_len(0)
+        .and_then(|_| file.seek(SeekFrom::Start(0)))
+        .and_then(|_| file.write_all(contents.as_bytes()))
+        .and_then(|_| file.sync_all())
+        .map_err(into_lease_store_error)
+}
+
+// first fit over the gaps between unexpired leases in the pool
+pub(crate) fn acquire_from_leases(
+    leases: &mut Vec<VolumeLease>,
+    pool_base: u64,
+    pool_length: u64,
+    holder: &str,
+    logical_volume_length: u64,
+    ttl_ms: u64,
+    now_ms: u64,
+) -> Result<VolumeLease, Error> {
+    check_lease_request(
+        pool_base,
+        pool_length,
+        holder,
+        logical_volume_length,
+        ttl_ms,
+    )?;
+
+    let expires_at_ms = get_expires_at_ms(now_ms, ttl_ms)?;
+    leases.retain(|lease| now_ms < lease.expires_at_ms);
+    leases.sort_by_key(|lease| lease.logical_volume_base);
+
+    // check_lease_request keeps the pool and the request within MAX_LOGICAL_VOLUMES,
+    // stored leases may not be
+    let mut logical_volume_base = pool_base;
+    for lease in leases.iter() {
+        if logical_volume_base.saturating_add(logical_volume_length) <= lease.logical_volume_base {
+            break;
+        }
+        logical_volume_base = logical_volume_base.max(
+            lease
+                .logical_volume_base
+                .saturating_add(lease.logical_volume_length),
+        );
+    }
+    if pool_base + pool_length < logical_volume_base.saturating_add(logical_volume_length) {
+        return Err(Error::NoLogicalVolumesToLease {
+            logical_volume_length,
+        });
+    }
+
+    let lease = VolumeLease {
+        holder: holder.to_string(),
+        logical_volume_base,
+        logical_volume_length,
+        expires_at_ms,
+    };
+    leases.push(lease.clone());
+    Ok(lease)
+}
+
+// a lease that already expired may belong to someone else by now
+pub(crate) fn heartbeat_in_leases(
+    leases: &mut [VolumeLease],
+    lease: &VolumeLease,
+    ttl_ms: u64,
+    now_ms: u64,
+) -> Result<VolumeLease, Error> {
+    let expires_at_ms = get_expires_at_ms(now_ms, ttl_ms)?;
+    let held = leases.iter_mut().find(|held| {
+        held.holder == lease.holder
+            && held.logical_volume_base == lease.logical_volume_base
+            && held.logical_volume_length == lease.logical_volume_length
+            && now_ms < held.expires_at_ms
+    });
+    match held {
+        Some(held) => {
+            held.expires_at_ms = expires_at_ms;
+            Ok(held.clone())
+        }
+        _ => Err(Error::LeaseLapsed {
+            expires_at_ms: lease.expires_at_ms,
+        }),
+    }
+}
+
+// expired leases are dropped by the next acquire
+pub(crate) fn release_from_leases(leases: &mut [VolumeLease], lease: &VolumeLease) {
+    for held in leases.iter_mut() {
+        if held.holder == lease.holder && held.logical_volume_base == lease.logical_volume_base {
+            held.expires_at_ms = held.expires_at_ms.min(lease.expires_at_ms);
+        }
+    }
+}
+
+pub(crate) fn check_lease_request(
+    pool_base: u64,
+    pool_length: u64,
+    holder: &str,
+    logical_volume_length: u64,
+    ttl_ms: u64,
+) -> Result<(), Error> {
+    let is_exceeded = match pool_base.checked_add(pool_length) {
+        Some(pool_end) => MAX_LOGICAL_VOLUMES < pool_end,
+        _ => true,
+    };
+    if is_exceeded {
+        return Err(Error::ExceededAvailableLogicalVolumes {
+            logical_volume_base: pool_base,
+            logical_volume_length: pool_length,
+        });
+    }
+    if logical_volume_length == 0 {
+        return Err(Error::LogicalVolumeModuloIsZero);
+    }
+    if pool_length < logical_volume_length {
+        return Err(Error::NoLogicalVolumesToLease {
+            logical_volume_length,
+        });
+    }
+    if ttl_ms == 0 {
+        return Err(Error::LeaseTtlIsZero);
+    }
+    if holder.is_empty() || holder.contains(['\n', '\r']) {
+        return Err(Error::InvalidLeaseHolder);
+    }
+
+    Ok(())
+}
+
+// sqlite stores expiries as i64, so every store keeps them within i64::MAX
+pub(crate) fn get_expires_at_ms(now_ms: u64, ttl_ms: u64) -> Result<u64, Error> {
+    match now_ms.checked_add(ttl_ms) {
+        Some(expires_at_ms) if expires_at_ms <= i64::MAX as u64 => Ok(expires_at_ms),
+        _ => Err(Error::ExceededLeaseTtl { ttl_ms }),
+    }
+}
+
+fn into_lease_store_error(err: io::Error) -> Error {
+    Error::FailedToAccessLeaseStore { kind: err.kind() }
+}
+
+pub(crate) fn get_unix_ms(system_time: SystemTime) -> u64 {
+    match system_time.duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_millis() as u64,
+        _ => 0,
+    }
+}
+
+#[derive(Debug)]
+pub struct LeasedSnowprint<S: VolumeLeaseStore, C: Clock + Clone = SystemClock> {
+    snowprint: Snowprint<C>,
+    store: S,
+    clock: C,
+    lease: VolumeLease,
+    ttl_ms: u64,
+    origin_ms: u64,
+    is_lapsed: bool,
+}
+
+impl<S: VolumeLeaseStore> LeasedSnowprint<S> {
+    pub fn new(
+        settings: Settings,
+        store: S,
+        holder: &str,
+        ttl_ms: u64,
+    ) -> Result<LeasedSnowprint<S>, Error> {
+        LeasedSnowprint::with_clock(settings, store, holder, ttl_ms, SystemClock)
+    }
+}
+
+impl<S: VolumeLeaseStore, C: Clock + Clone> LeasedSnowprint<S, C> {
+    // logical_volume_length is how many volumes to lease,
+    // the base and any logical_volume_set are replaced by the lease
+    pub fn with_clock(
+        settings: Settings,
+        mut store: S,
+        holder: &str,
+        ttl_ms: u64,
+        clock: C,
+    ) -> Result<LeasedSnowprint<S, C>, Error> {
+        let now_ms = get_unix_ms(clock.now());
+        let lease = store.acquire(holder, settings.logical_volume_length, ttl_ms, now_ms)?;
+
+        let settings = Settings {
+            logical_volume_base: lease.logical_volume_base,
+            logical_volume_length: lease.logical_volume_length,
+            logical_volume_set: None,
+            ..settings
+        };
+        let origin_ms = get_unix_ms(settings.origin_system_time);
+        let snowprint = match Snowprint::with_clock(settings, clock.clone()) {
+            Ok(snowprint) => snowprint,
+            // nothing was composed, so the volumes are free right away
+            Err(err) => {
+                let _ = store.release(&VolumeLease {
+                    expires_at_ms: now_ms,
+                    ..lease
+                });
+                return Err(err);
+            }
+        };
+
+        Ok(LeasedSnowprint {
+            snowprint,
+            store,
+            clock,
+            lease,
+            ttl_ms,
+            origin_ms,
+            is_lapsed: false,
+        })
+    }
+
+    // heartbeats once half the ttl has passed, ids are only returned from before expiry
+    pub fn compose(&mut self) -> Result<SnowprintId, Error> {
+        let now_ms = self.check_lease()?;
+        if self.lease.expires_at_ms <= now_ms.saturating_add(self.ttl_ms / 2) {
+            // an unreachable store is tried again next compose, the lease is still good
+            if let Err(err @ Error::LeaseLapsed { .. }) = self.heartbeat() {
+                return Err(err);
+            }
+        }
+
+        let snowprint = self.snowprint.compose()?;
+        if self.lease.expires_at_ms <= self.origin_ms + snowprint.timestamp_ms() {
+            self.is_lapsed = true;
+            return Err(Error::LeaseLapsed {
+                expires_at_ms: self.lease.expires_at_ms,
+            });
+        }
+
+        Ok(snowprint)
+    }
+
+    // idle generators call this within every ttl to keep their volumes
+    pub fn heartbeat(&mut self) -> Result<(), Error> {
+        let now_ms = self.check_lease()?;
+        match self.store.heartbeat(&self.lease, self.ttl_ms, now_ms) {
+            Ok(lease) => {
+                self.lease = lease;
+                Ok(())
+            }
+            Err(err) => {
+                if let Error::LeaseLapsed { .. } = err {
+                    self.is_lapsed = true;
+                }
+                Err(err)
+            }
+        }
+    }
+
+    pub fn lease(&self) -> &VolumeLease {
+        &self.lease
+    }
+
+    pub fn is_lapsed(&self) -> bool {
+        self.is_lapsed
+    }
+
+    // frees the volumes once the last ms an id could have been composed on has passed,
+    // instead of waiting for expiry
+    pub fn release(mut self) -> Result<S, Error> {
+        if !self.is_lapsed {
+            let now_ms = get_unix_ms(self.clock.now());
+            let composed_ms = self.origin_ms + self.snowprint.snapshot().prev_duration_ms;
+            let lease = VolumeLease {
+                expires_at_ms: now_ms.max(composed_ms) + 1,
+                ..self.lease.clone()
+            };
+            self.store.release(&lease)?;
+        }
+        Ok(self.store)
+    }
+
+    // a lapsed lease never comes back, another holder may own the volumes now
+    fn check_lease(&mut self) -> Result<u64, Error> {
+        let now_ms = get_unix_ms(self.clock.now());
+        if self.is_lapsed || self.lease.expires_at_ms <= now_ms {
+            self.is_lapsed = true;
+            return Err(Error::LeaseLapsed {
+                expires_at_ms: self.lease.expires_at_ms,
+            });
+        }
+
+        Ok(now_ms)
+    }
+}
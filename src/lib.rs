@@ -6,10 +6,65 @@
 // This assumes sequences + logical volume ids occur in the same ms
 // https://instagram-engineering.com/sharding-ids-at-instagram-1cf5a71e5a5c
 
+mod affinity;
+#[cfg(feature = "async")]
+mod async_snowprint;
+mod audit;
+mod bounds;
+mod clock;
+mod encoding;
+mod exhaustion;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+mod hash;
+mod id;
+mod lease;
+#[cfg(feature = "obfuscation")]
+mod obfuscation;
+mod persist;
+mod range;
+pub mod rfc3339;
+#[cfg(feature = "serde")]
+pub mod serialization;
+#[cfg(feature = "server")]
+pub mod server;
+mod shard;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod sync;
 #[cfg(test)]
 mod test;
+mod volume;
+mod worker;
 
-use std::time::SystemTime;
+#[cfg(feature = "async")]
+pub use async_snowprint::AsyncSnowprint;
+pub use audit::{AuditReport, AuditSettings, Auditor, Finding};
+pub use bounds::{lower_bound, range_for, range_for_logical_volume, upper_bound};
+pub use clock::{Clock, ClockSkewHook, ManualClock, MonotonicClock, SystemClock};
+pub use encoding::Encoding;
+pub use exhaustion::ExhaustionPolicy;
+pub use id::SnowprintId;
+pub use lease::{FileLeaseStore, LeasedSnowprint, MemoryLeaseStore, VolumeLease, VolumeLeaseStore};
+#[cfg(feature = "obfuscation")]
+pub use obfuscation::Obfuscator;
+pub use persist::{FileStateStore, PersistentSnowprint, StateStore};
+pub use range::SnowprintRange;
+pub use shard::{ShardMap, ShardMove, ShardRange};
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteLeaseStore;
+pub use sync::SyncSnowprint;
+pub use volume::{LogicalVolumeSet, WeightedLogicalVolume};
+
+use affinity::{
+    compose_from_settings_and_volume_state, compose_on_logical_volume_from_settings_and_state,
+    hash_key, VolumeSequences,
+};
+use exhaustion::wait_for_next_ms;
+use std::fmt;
+use std::io;
+use std::time::{SystemTime, UNIX_EPOCH};
+use volume::check_logical_volume_set;
 
 const SEQUENCE_BIT_LEN: u64 = 10;
 const SEQUENCE_BIT_MASK: u64 = (1 << SEQUENCE_BIT_LEN) - 1;
@@ -17,6 +72,8 @@ const MAX_SEQUENCES: u64 = u32::pow(2, SEQUENCE_BIT_LEN as u32) as u64;
 const LOGICAL_VOLUME_BIT_LEN: u64 = 13;
 const LOGICAL_VOLUME_BIT_MASK: u64 = ((1 << LOGICAL_VOLUME_BIT_LEN) - 1) << SEQUENCE_BIT_LEN;
 const MAX_LOGICAL_VOLUMES: u64 = u32::pow(2, LOGICAL_VOLUME_BIT_LEN as u32) as u64;
+const TIMESTAMP_BIT_LEN: u64 = 64 - LOGICAL_VOLUME_BIT_LEN - SEQUENCE_BIT_LEN;
+const MAX_TIMESTAMP: u64 = (1 << TIMESTAMP_BIT_LEN) - 1;
 
 // core functionality of snowprints
 pub fn compose(ms_timestamp: u64, logical_volume: u64, ticket_id: u64) -> u64 {
@@ -25,6 +82,25 @@ pub fn compose(ms_timestamp: u64, logical_volume: u64, ticket_id: u64) -> u64 {
         | ticket_id
 }
 
+// like compose but refuses values that would bleed into neighbouring fields
+pub fn try_compose(ms_timestamp: u64, logical_volume: u64, ticket_id: u64) -> Result<u64, Error> {
+    if MAX_TIMESTAMP < ms_timestamp {
+        return Err(Error::ExceededTimestampBitLength {
+            timestamp_ms: ms_timestamp,
+        });
+    }
+    if MAX_LOGICAL_VOLUMES <= logical_volume {
+        return Err(Error::ExceededLogicalVolumeBitLength { logical_volume });
+    }
+    if MAX_SEQUENCES <= ticket_id {
+        return Err(Error::ExceededSequenceBitLength {
+            sequence: ticket_id,
+        });
+    }
+
+    Ok(compose(ms_timestamp, logical_volume, ticket_id))
+}
+
 pub fn decompose(snowprint: u64) -> (u64, u64, u64) {
     let time = snowprint >> (LOGICAL_VOLUME_BIT_LEN + SEQUENCE_BIT_LEN);
     let logical_volume = (snowprint & LOGICAL_VOLUME_BIT_MASK) >> SEQUENCE_BIT_LEN;
@@ -37,15 +113,262 @@ pub fn decompose(snowprint: u64) -> (u64, u64, u64) {
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum Error {
     LogicalVolumeModuloIsZero,
-    ExceededAvailableLogicalVolumes,
+    ExceededAvailableLogicalVolumes {
+        logical_volume_base: u64,
+        logical_volume_length: u64,
+    },
     FailedToParseOriginSystemTime,
     ExceededAvailableSequences,
+    ExceededTimestampBitLength {
+        timestamp_ms: u64,
+    },
+    ExceededLogicalVolumeBitLength {
+        logical_volume: u64,
+    },
+    ExceededSequenceBitLength {
+        sequence: u64,
+    },
+    FailedToLoadHighWaterMark {
+        kind: io::ErrorKind,
+    },
+    FailedToStoreHighWaterMark {
+        kind: io::ErrorKind,
+    },
+    ClockBehindHighWaterMark {
+        duration_ms: u64,
+        high_water_mark: u64,
+    },
+    ClockMovedBackwards {
+        by_ms: u64,
+    },
+    InvalidStateSnapshot,
+    InvalidEncodedLength {
+        encoding: Encoding,
+        length: usize,
+    },
+    InvalidEncodedCharacter {
+        encoding: Encoding,
+        character: char,
+    },
+    ExceededEncodedRange {
+        encoding: Encoding,
+    },
+    OverlappingShardRanges {
+        logical_volume: u64,
+    },
+    EmptyLogicalVolumeSet,
+    DuplicateLogicalVolume {
+        logical_volume: u64,
+    },
+    UnorderedLogicalVolumeSet {
+        logical_volume: u64,
+    },
+    LogicalVolumeWeightIsZero {
+        logical_volume: u64,
+    },
+    ExceededTotalLogicalVolumeWeight,
+    LogicalVolumeNotInSettings {
+        logical_volume: u64,
+    },
+    LeasedLogicalVolumeInSettings {
+        logical_volume: u64,
+    },
+    NoLogicalVolumesToLease {
+        logical_volume_length: u64,
+    },
+    LeaseLapsed {
+        expires_at_ms: u64,
+    },
+    LeaseTtlIsZero,
+    ExceededLeaseTtl {
+        ttl_ms: u64,
+    },
+    InvalidLeaseHolder,
+    FailedToAccessLeaseStore {
+        kind: io::ErrorKind,
+    },
+    MissingWorkerId,
+    FailedToParseWorkerId,
 }
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::LogicalVolumeModuloIsZero => {
+                write!(f, "logical_volume_length must be greater than 0")
+            }
+            Error::ExceededAvailableLogicalVolumes {
+                logical_volume_base,
+                logical_volume_length,
+            } => write!(
+                f,
+                "logical_volume_base {} + logical_volume_length {} exceeds {} available logical volumes",
+                logical_volume_base, logical_volume_length, MAX_LOGICAL_VOLUMES
+            ),
+            Error::FailedToParseOriginSystemTime => {
+                write!(f, "origin_system_time is later than the current time")
+            }
+            Error::ExceededAvailableSequences => write!(
+                f,
+                "consumed all available logical volumes and sequences for the current ms"
+            ),
+            Error::ExceededTimestampBitLength { timestamp_ms } => write!(
+                f,
+                "timestamp {}ms exceeds the maximum of {}ms",
+                timestamp_ms, MAX_TIMESTAMP
+            ),
+            Error::ExceededLogicalVolumeBitLength { logical_volume } => write!(
+                f,
+                "logical volume {} exceeds the maximum of {}",
+                logical_volume,
+                MAX_LOGICAL_VOLUMES - 1
+            ),
+            Error::ExceededSequenceBitLength { sequence } => write!(
+                f,
+                "sequence {} exceeds the maximum of {}",
+                sequence,
+                MAX_SEQUENCES - 1
+            ),
+            Error::FailedToLoadHighWaterMark { kind } => {
+                write!(f, "failed to load high water mark: {}", kind)
+            }
+            Error::FailedToStoreHighWaterMark { kind } => {
+                write!(f, "failed to store high water mark: {}", kind)
+            }
+            Error::ClockBehindHighWaterMark {
+                duration_ms,
+                high_water_mark,
+            } => write!(
+                f,
+                "clock at {}ms has not passed the high water mark of {}ms",
+                duration_ms, high_water_mark
+            ),
+            Error::ClockMovedBackwards { by_ms } => {
+                write!(f, "clock moved backwards by {}ms", by_ms)
+            }
+            Error::InvalidStateSnapshot => {
+                write!(f, "state snapshot does not fit within settings")
+            }
+            Error::InvalidEncodedLength { encoding, length } => write!(
+                f,
+                "{} snowprints are {} characters, found {}",
+                encoding,
+                encoding.width(),
+                length
+            ),
+            Error::InvalidEncodedCharacter {
+                encoding,
+                character,
+            } => write!(f, "{:?} is not a {} digit", character, encoding),
+            Error::ExceededEncodedRange { encoding } => {
+                write!(f, "{} snowprint exceeds 64 bits", encoding)
+            }
+            Error::OverlappingShardRanges { logical_volume } => write!(
+                f,
+                "logical volume {} is assigned to more than one shard",
+                logical_volume
+            ),
+            Error::EmptyLogicalVolumeSet => {
+                write!(f, "logical_volume_set must not be empty")
+            }
+            Error::DuplicateLogicalVolume { logical_volume } => write!(
+                f,
+                "logical volume {} appears more than once in logical_volume_set",
+                logical_volume
+            ),
+            Error::UnorderedLogicalVolumeSet { logical_volume } => write!(
+                f,
+                "logical volume {} is not larger than the one before it in logical_volume_set",
+                logical_volume
+            ),
+            Error::LogicalVolumeWeightIsZero { logical_volume } => {
+                write!(f, "logical volume {} has a weight of 0", logical_volume)
+            }
+            Error::ExceededTotalLogicalVolumeWeight => {
+                write!(f, "logical volume weights add up to more than {}", u64::MAX)
+            }
+            Error::LogicalVolumeNotInSettings { logical_volume } => write!(
+                f,
+                "logical volume {} is not rotated by these settings",
+                logical_volume
+            ),
+            Error::LeasedLogicalVolumeInSettings { logical_volume } => write!(
+                f,
+                "logical volume {} is leased to clients but also rotated by these settings",
+                logical_volume
+            ),
+            Error::NoLogicalVolumesToLease {
+                logical_volume_length,
+            } => write!(
+                f,
+                "no run of {} free logical volumes is left to lease",
+                logical_volume_length
+            ),
+            Error::LeaseLapsed { expires_at_ms } => {
+                write!(f, "logical volume lease expired at {}ms", expires_at_ms)
+            }
+            Error::LeaseTtlIsZero => write!(f, "lease ttl must be greater than 0"),
+            Error::ExceededLeaseTtl { ttl_ms } => {
+                write!(f, "lease ttl {} ms expires past the latest unix ms", ttl_ms)
+            }
+            Error::InvalidLeaseHolder => {
+                write!(f, "lease holder must be a single non-empty line")
+            }
+            Error::FailedToAccessLeaseStore { kind } => {
+                write!(f, "failed to access lease store: {}", kind)
+            }
+            Error::MissingWorkerId => write!(f, "worker id is not set"),
+            Error::FailedToParseWorkerId => {
+                write!(f, "worker id is not a non-negative integer")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct Settings {
+    #[cfg_attr(feature = "serde", serde(with = "serialization::origin_system_time"))]
     pub origin_system_time: SystemTime,
     pub logical_volume_base: u64,
     pub logical_volume_length: u64,
+    // replaces logical_volume_base and logical_volume_length when set
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub logical_volume_set: Option<LogicalVolumeSet>,
+    pub exhaustion_policy: ExhaustionPolicy,
+    pub max_backward_skew_ms: Option<u64>,
+    // every snowprint is larger than the last, each ms ends before wrapping to the first logical volume
+    pub strictly_increasing: bool,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub clock_skew_hook: Option<ClockSkewHook>,
+}
+
+impl Settings {
+    // the logical volume Snowprint::compose_for_key picks for a key, weights are ignored
+    pub fn logical_volume_for_key(&self, key: impl AsRef<[u8]>) -> Result<u64, Error> {
+        check_settings(self)?;
+
+        let logical_volume = hash_key(key.as_ref()) % get_logical_volume_length(self);
+        Ok(get_logical_volume(self, logical_volume))
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            origin_system_time: UNIX_EPOCH,
+            logical_volume_base: 0,
+            logical_volume_length: MAX_LOGICAL_VOLUMES,
+            logical_volume_set: None,
+            exhaustion_policy: ExhaustionPolicy::default(),
+            max_backward_skew_ms: None,
+            strictly_increasing: false,
+            clock_skew_hook: None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -56,67 +379,332 @@ struct State {
     pub prev_logical_volume: u64,
 }
 
+// a copy of generator state that can be stored and restored later
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StateSnapshot {
+    pub prev_duration_ms: u64,
+    pub sequence: u64,
+    pub logical_volume: u64,
+    pub prev_logical_volume: u64,
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
-pub struct Snowprint {
+pub struct Snowprint<C: Clock = SystemClock> {
     settings: Settings,
     state: State,
+    volume_sequences: VolumeSequences,
+    clock: C,
 }
 
 impl Snowprint {
     pub fn new(settings: Settings) -> Result<Snowprint, Error> {
-        if let Err(err) = check_settings(&settings) {
-            return Err(err);
-        }
+        Snowprint::with_clock(settings, SystemClock)
+    }
+}
 
-        let duration_ms = match SystemTime::now().duration_since(settings.origin_system_time) {
-            Ok(duration) => duration.as_millis() as u64,
-            _ => return Err(Error::FailedToParseOriginSystemTime),
-        };
+impl<C: Clock> Snowprint<C> {
+    pub fn with_clock(settings: Settings, clock: C) -> Result<Snowprint<C>, Error> {
+        check_settings(&settings)?;
+
+        let duration_ms = get_initial_duration_ms(&clock, settings.origin_system_time)?;
+        let prev_logical_volume = get_initial_prev_logical_volume(&settings);
 
         Ok(Snowprint {
-            settings: settings,
+            settings,
             state: State {
                 prev_duration_ms: duration_ms,
                 sequence: 0,
                 logical_volume: 0,
+                prev_logical_volume,
+            },
+            volume_sequences: VolumeSequences::default(),
+            clock,
+        })
+    }
+
+    // nothing is composed until the clock moves past the snapshot's ms,
+    // the snapshot doesn't know which sequences were used on chosen logical volumes
+    pub fn from_snapshot(
+        settings: Settings,
+        snapshot: StateSnapshot,
+        clock: C,
+    ) -> Result<Snowprint<C>, Error> {
+        check_settings(&settings)?;
+        check_snapshot(&settings, &snapshot)?;
+
+        // the rotation ends the snapshot's ms on the last index, so strictly increasing
+        // settings can't move it forward either
+        let logical_volume_length = get_logical_volume_length(&settings);
+        Ok(Snowprint {
+            settings,
+            state: State {
+                prev_duration_ms: snapshot.prev_duration_ms,
+                sequence: MAX_SEQUENCES - 1,
+                logical_volume: logical_volume_length - 1,
                 prev_logical_volume: 0,
             },
+            volume_sequences: VolumeSequences::exhausted(snapshot.prev_duration_ms),
+            clock,
         })
     }
 
-    pub fn compose(&mut self) -> Result<u64, Error> {
-        let duration_ms = get_most_recent_duration_ms(
-            self.settings.origin_system_time,
-            self.state.prev_duration_ms,
-        );
-        compose_from_settings_and_state(&self.settings, &mut self.state, duration_ms)
+    // prev_duration_ms is the latest ms composed on, including chosen logical volumes
+    pub fn snapshot(&self) -> StateSnapshot {
+        StateSnapshot {
+            prev_duration_ms: self
+                .state
+                .prev_duration_ms
+                .max(self.volume_sequences.duration_ms()),
+            sequence: self.state.sequence,
+            logical_volume: self.state.logical_volume,
+            prev_logical_volume: self.state.prev_logical_volume,
+        }
+    }
+
+    pub fn compose(&mut self) -> Result<SnowprintId, Error> {
+        self.compose_with_exhaustion_policy(self.settings.exhaustion_policy)
+    }
+
+    // waits for the next ms even when settings say to return an error
+    pub fn compose_blocking(&mut self) -> Result<SnowprintId, Error> {
+        let exhaustion_policy = match self.settings.exhaustion_policy {
+            ExhaustionPolicy::ReturnError => ExhaustionPolicy::Sleep,
+            exhaustion_policy => exhaustion_policy,
+        };
+        self.compose_with_exhaustion_policy(exhaustion_policy)
+    }
+
+    // reserves `length` snowprints of the current ms, or none at all
+    pub fn reserve(&mut self, length: usize) -> Result<SnowprintRange, Error> {
+        let duration_ms = self.get_most_recent_duration_ms()?;
+        reserve_from_settings_and_state(
+            &self.settings,
+            &mut self.state,
+            &self.volume_sequences,
+            duration_ms,
+            length,
+            false,
+        )
+    }
+
+    pub fn compose_batch(&mut self, length: usize) -> Result<Vec<SnowprintId>, Error> {
+        let mut snowprints = vec![SnowprintId::from(0); length];
+        self.fill(&mut snowprints)?;
+        Ok(snowprints)
+    }
+
+    // with ExhaustionPolicy::ReturnError the snowprints must fit in the current ms,
+    // otherwise snowprints spill into later ms as they become available
+    pub fn fill(&mut self, snowprints: &mut [SnowprintId]) -> Result<(), Error> {
+        let exhaustion_policy = self.settings.exhaustion_policy;
+        if exhaustion_policy == ExhaustionPolicy::ReturnError {
+            let range = self.reserve(snowprints.len())?;
+            for (snowprint, reserved) in snowprints.iter_mut().zip(range) {
+                *snowprint = reserved;
+            }
+            return Ok(());
+        }
+
+        let mut filled = 0;
+        while filled < snowprints.len() {
+            let duration_ms = self.get_most_recent_duration_ms()?;
+            let range = match reserve_from_settings_and_state(
+                &self.settings,
+                &mut self.state,
+                &self.volume_sequences,
+                duration_ms,
+                snowprints.len() - filled,
+                true,
+            ) {
+                Ok(range) => range,
+                Err(Error::ExceededAvailableSequences) => {
+                    wait_for_next_ms(
+                        &self.clock,
+                        self.settings.origin_system_time,
+                        duration_ms,
+                        exhaustion_policy,
+                    );
+                    continue;
+                }
+                Err(err) => return Err(err),
+            };
+            for reserved in range {
+                snowprints[filled] = reserved;
+                filled += 1;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn compose_with_exhaustion_policy(
+        &mut self,
+        exhaustion_policy: ExhaustionPolicy,
+    ) -> Result<SnowprintId, Error> {
+        loop {
+            let duration_ms = self.get_most_recent_duration_ms()?;
+            match compose_from_settings_and_volume_state(
+                &self.settings,
+                &mut self.state,
+                &self.volume_sequences,
+                duration_ms,
+            ) {
+                Err(Error::ExceededAvailableSequences)
+                    if exhaustion_policy != ExhaustionPolicy::ReturnError =>
+                {
+                    wait_for_next_ms(
+                        &self.clock,
+                        self.settings.origin_system_time,
+                        duration_ms,
+                        exhaustion_policy,
+                    );
+                }
+                snowprint => return snowprint.map(SnowprintId::from),
+            }
+        }
+    }
+
+    // snowprints with the same key share a logical volume, and so a shard
+    pub fn compose_for_key(&mut self, key: impl AsRef<[u8]>) -> Result<SnowprintId, Error> {
+        let logical_volume = hash_key(key.as_ref()) % get_logical_volume_length(&self.settings);
+        self.compose_on_logical_volume(logical_volume)
+    }
+
+    // the parent's logical volume must belong to these settings, or another
+    // generator could compose the same snowprint
+    pub fn compose_child_of(&mut self, parent: SnowprintId) -> Result<SnowprintId, Error> {
+        let logical_volume = parent.logical_volume();
+        match get_logical_volume_index(&self.settings, logical_volume) {
+            Some(index) => self.compose_on_logical_volume(index),
+            _ => Err(Error::LogicalVolumeNotInSettings { logical_volume }),
+        }
+    }
+
+    // `logical_volume` is an index into the logical volumes in settings
+    fn compose_on_logical_volume(&mut self, logical_volume: u64) -> Result<SnowprintId, Error> {
+        let exhaustion_policy = self.settings.exhaustion_policy;
+        loop {
+            let duration_ms = self.get_most_recent_duration_ms()?;
+            match compose_on_logical_volume_from_settings_and_state(
+                &self.settings,
+                &mut self.state,
+                &mut self.volume_sequences,
+                duration_ms,
+                logical_volume,
+            ) {
+                Err(Error::ExceededAvailableSequences)
+                    if exhaustion_policy != ExhaustionPolicy::ReturnError =>
+                {
+                    wait_for_next_ms(
+                        &self.clock,
+                        self.settings.origin_system_time,
+                        duration_ms,
+                        exhaustion_policy,
+                    );
+                }
+                snowprint => return snowprint.map(SnowprintId::from),
+            }
+        }
+    }
+
+    // chosen logical volumes may have composed on a later ms than the rotation
+    fn get_most_recent_duration_ms(&self) -> Result<u64, Error> {
+        let prev_duration_ms = self
+            .state
+            .prev_duration_ms
+            .max(self.volume_sequences.duration_ms());
+        get_most_recent_duration_ms(&self.clock, &self.settings, prev_duration_ms)
     }
 }
 
 fn check_settings(settings: &Settings) -> Result<(), Error> {
+    if let Some(logical_volume_set) = &settings.logical_volume_set {
+        check_logical_volume_set(logical_volume_set)?;
+        // the rotation follows the order given, so it has to be ascending
+        if settings.strictly_increasing {
+            for pair in logical_volume_set.logical_volumes().windows(2) {
+                if pair[1] < pair[0] {
+                    return Err(Error::UnorderedLogicalVolumeSet {
+                        logical_volume: pair[1],
+                    });
+                }
+            }
+        }
+        return Ok(());
+    }
     if settings.logical_volume_length == 0 {
         return Err(Error::LogicalVolumeModuloIsZero);
     }
-    if MAX_LOGICAL_VOLUMES < (settings.logical_volume_base + settings.logical_volume_length) {
-        return Err(Error::ExceededAvailableLogicalVolumes);
+    // a base derived from a worker id may be large enough to overflow
+    let is_exceeded = match settings
+        .logical_volume_base
+        .checked_add(settings.logical_volume_length)
+    {
+        Some(logical_volume_end) => MAX_LOGICAL_VOLUMES < logical_volume_end,
+        _ => true,
+    };
+    if is_exceeded {
+        return Err(Error::ExceededAvailableLogicalVolumes {
+            logical_volume_base: settings.logical_volume_base,
+            logical_volume_length: settings.logical_volume_length,
+        });
     }
 
     Ok(())
 }
 
-fn get_most_recent_duration_ms(origin_system_time: SystemTime, prev_duration_ms: u64) -> u64 {
-    match SystemTime::now().duration_since(origin_system_time) {
-        // check time didn't go backward
+fn check_snapshot(settings: &Settings, snapshot: &StateSnapshot) -> Result<(), Error> {
+    if MAX_TIMESTAMP < snapshot.prev_duration_ms
+        || MAX_SEQUENCES <= snapshot.sequence
+        || get_logical_volume_length(settings) <= snapshot.logical_volume
+        || get_logical_volume_length(settings) <= snapshot.prev_logical_volume
+    {
+        return Err(Error::InvalidStateSnapshot);
+    }
+
+    Ok(())
+}
+
+fn get_initial_duration_ms(
+    clock: &impl Clock,
+    origin_system_time: SystemTime,
+) -> Result<u64, Error> {
+    match clock.now().duration_since(origin_system_time) {
+        Ok(duration) => Ok(duration.as_millis() as u64),
+        _ => Err(Error::FailedToParseOriginSystemTime),
+    }
+}
+
+fn get_most_recent_duration_ms(
+    clock: &impl Clock,
+    settings: &Settings,
+    prev_duration_ms: u64,
+) -> Result<u64, Error> {
+    let (duration_ms, skew_ms) = match clock.now().duration_since(settings.origin_system_time) {
         Ok(duration) => {
             let dur_ms = duration.as_millis() as u64;
-            match prev_duration_ms < dur_ms {
-                true => dur_ms,
-                _ => prev_duration_ms,
-            }
+            (dur_ms, prev_duration_ms.saturating_sub(dur_ms))
         }
-        // yikes! time went backwards so use the most recent step
-        _ => prev_duration_ms,
+        // yikes! time went backwards past the origin
+        Err(err) => (0, prev_duration_ms + err.duration().as_millis() as u64),
+    };
+
+    // check time didn't go backward
+    if skew_ms == 0 {
+        return Ok(duration_ms);
     }
+    if let Some(clock_skew_hook) = &settings.clock_skew_hook {
+        clock_skew_hook.call(skew_ms);
+    }
+    if let Some(max_backward_skew_ms) = settings.max_backward_skew_ms {
+        if max_backward_skew_ms < skew_ms {
+            return Err(Error::ClockMovedBackwards { by_ms: skew_ms });
+        }
+    }
+
+    // time went backwards within tolerance so use the most recent step
+    Ok(prev_duration_ms)
 }
 
 fn compose_from_settings_and_state(
@@ -124,22 +712,147 @@ fn compose_from_settings_and_state(
     state: &mut State,
     duration_ms: u64,
 ) -> Result<u64, Error> {
+    let logical_volume_length = get_logical_volume_length(settings);
     match state.prev_duration_ms < duration_ms {
-        true => modify_state_time_changed(state, settings.logical_volume_length, duration_ms),
-        _ => {
-            if let Err(err) =
-                modify_state_time_did_not_change(state, settings.logical_volume_length)
-            {
-                return Err(err);
-            };
+        true => {
+            state.logical_volume =
+                get_prev_logical_volume(settings, state.logical_volume, duration_ms);
+            modify_state_time_changed(state, logical_volume_length, duration_ms);
+            state.prev_logical_volume =
+                get_stop_logical_volume(settings, state.prev_logical_volume);
         }
+        _ => modify_state_time_did_not_change(state, logical_volume_length)?,
     }
 
-    Ok(compose(
+    try_compose(
         duration_ms,
-        settings.logical_volume_base + state.logical_volume,
+        get_logical_volume(settings, state.logical_volume),
         state.sequence,
-    ))
+    )
+}
+
+fn get_logical_volume_length(settings: &Settings) -> u64 {
+    match &settings.logical_volume_set {
+        Some(logical_volume_set) => logical_volume_set.len() as u64,
+        _ => settings.logical_volume_length,
+    }
+}
+
+// state tracks an index, this is the logical volume that goes into the snowprint
+fn get_logical_volume(settings: &Settings, index: u64) -> u64 {
+    match &settings.logical_volume_set {
+        Some(logical_volume_set) => logical_volume_set.get(index),
+        _ => settings.logical_volume_base + index,
+    }
+}
+
+fn get_logical_volume_index(settings: &Settings, logical_volume: u64) -> Option<u64> {
+    match &settings.logical_volume_set {
+        Some(logical_volume_set) => logical_volume_set
+            .logical_volumes()
+            .iter()
+            .position(|&candidate| candidate == logical_volume)
+            .map(|index| index as u64),
+        _ => match logical_volume.checked_sub(settings.logical_volume_base) {
+            Some(index) if index < settings.logical_volume_length => Some(index),
+            _ => None,
+        },
+    }
+}
+
+// a new ms starts on the index after this one and exhausts before returning to it
+fn get_prev_logical_volume(settings: &Settings, logical_volume: u64, duration_ms: u64) -> u64 {
+    let logical_volume_set = match &settings.logical_volume_set {
+        Some(logical_volume_set) => logical_volume_set,
+        _ => return logical_volume,
+    };
+    match logical_volume_set.pick(duration_ms) {
+        Some(index) => {
+            let logical_volume_length = logical_volume_set.len() as u64;
+            (index + logical_volume_length - 1) % logical_volume_length
+        }
+        _ => logical_volume,
+    }
+}
+
+// the first ms starts on index 0 as if the previous ms ended on the last index,
+// so like every other ms the rotation never lands on prev_logical_volume
+fn get_initial_prev_logical_volume(settings: &Settings) -> u64 {
+    get_stop_logical_volume(settings, get_logical_volume_length(settings) - 1)
+}
+
+// a ms exhausts before returning to this index
+//     - usually the index the previous ms ended on, so every logical volume is used
+//     - strictly increasing ms stop before wrapping back to index 0 instead
+fn get_stop_logical_volume(settings: &Settings, prev_logical_volume: u64) -> u64 {
+    match settings.strictly_increasing {
+        true => 0,
+        _ => prev_logical_volume,
+    }
+}
+
+// the first snowprint follows compose_from_settings_and_state, the rest follow in order
+// with `allow_fewer` the range stops at the end of the ms instead of returning an error
+fn reserve_from_settings_and_state(
+    settings: &Settings,
+    state: &mut State,
+    volume_sequences: &VolumeSequences,
+    duration_ms: u64,
+    length: usize,
+    allow_fewer: bool,
+) -> Result<SnowprintRange, Error> {
+    let mut next_state = state.clone();
+    if length > 0 {
+        compose_from_settings_and_volume_state(
+            settings,
+            &mut next_state,
+            volume_sequences,
+            duration_ms,
+        )?;
+    }
+
+    // a range cannot run into sequences already used on a chosen logical volume
+    let logical_volume_length = get_logical_volume_length(settings);
+    let available = volume_sequences.limit_available_sequences(
+        &next_state,
+        logical_volume_length,
+        get_available_sequences(&next_state, logical_volume_length),
+    ) + 1;
+    if available < length as u64 && !allow_fewer {
+        return Err(Error::ExceededAvailableSequences);
+    }
+    let length = length.min(available as usize);
+
+    let range = SnowprintRange::new(
+        settings,
+        next_state.prev_duration_ms,
+        next_state.logical_volume,
+        next_state.sequence,
+        length,
+    );
+    if length > 0 {
+        modify_state_skip_sequences(&mut next_state, logical_volume_length, length as u64 - 1);
+    }
+
+    *state = next_state;
+    Ok(range)
+}
+
+// sequences left in the ms after the current one, before reaching prev_logical_volume
+fn get_available_sequences(state: &State, logical_volume_length: u64) -> u64 {
+    let remaining_logical_volumes =
+        (state.prev_logical_volume + logical_volume_length - state.logical_volume - 1)
+            % logical_volume_length;
+
+    MAX_SEQUENCES - 1 - state.sequence + remaining_logical_volumes * MAX_SEQUENCES
+}
+
+// same as calling modify_state_time_did_not_change `skipped` times without exhausting
+fn modify_state_skip_sequences(state: &mut State, logical_volume_length: u64, skipped: u64) {
+    let sequences = state.sequence + skipped;
+    state.sequence = sequences % MAX_SEQUENCES;
+    state.logical_volume =
+        (state.logical_volume + sequences / MAX_SEQUENCES) % logical_volume_length;
 }
 
 fn modify_state_time_changed(state: &mut State, logical_volume_length: u64, duration_ms: u64) {
@@ -0,0 +1,76 @@
+// Opaque external ids behind the `obfuscation` feature
+//     - a keyed Feistel network permutes all 64 bit values, so every snowprint
+//       maps to exactly one opaque id and back
+//     - storage keeps the sortable snowprint, public APIs show the opaque id
+
+// This hides creation rate and logical volumes from casual inspection. It is not
+// a vetted cipher, use a real block cipher when ids must resist cryptanalysis.
+// The permutation must never change, opaque ids already handed out depend on it.
+
+use crate::hash::mix;
+use crate::SnowprintId;
+use std::fmt;
+
+const ROUNDS: usize = 8;
+const HALF_BIT_LEN: u64 = 32;
+const HALF_BIT_MASK: u64 = (1 << HALF_BIT_LEN) - 1;
+const GOLDEN_GAMMA: u64 = 0x9e3779b97f4a7c15;
+
+#[derive(Clone, Eq, PartialEq)]
+pub struct Obfuscator {
+    round_keys: [u64; ROUNDS],
+}
+
+impl Obfuscator {
+    pub fn new(key: u128) -> Obfuscator {
+        let key_low = key as u64;
+        let key_high = (key >> 64) as u64;
+
+        let mut round_keys = [0; ROUNDS];
+        for (round, round_key) in round_keys.iter_mut().enumerate() {
+            let gamma = GOLDEN_GAMMA.wrapping_mul(round as u64 + 1);
+            *round_key = mix(key_low ^ mix(key_high.wrapping_add(gamma)));
+        }
+
+        Obfuscator { round_keys }
+    }
+
+    pub fn obfuscate(&self, snowprint: u64) -> u64 {
+        let mut left = snowprint >> HALF_BIT_LEN;
+        let mut right = snowprint & HALF_BIT_MASK;
+        for round_key in self.round_keys {
+            (left, right) = (right, left ^ round(right, round_key));
+        }
+
+        left << HALF_BIT_LEN | right
+    }
+
+    pub fn reveal(&self, obfuscated: u64) -> u64 {
+        let mut left = obfuscated >> HALF_BIT_LEN;
+        let mut right = obfuscated & HALF_BIT_MASK;
+        for round_key in self.round_keys.into_iter().rev() {
+            (left, right) = (right ^ round(left, round_key), left);
+        }
+
+        left << HALF_BIT_LEN | right
+    }
+
+    pub fn obfuscate_id(&self, snowprint_id: SnowprintId) -> u64 {
+        self.obfuscate(snowprint_id.as_u64())
+    }
+
+    pub fn reveal_id(&self, obfuscated: u64) -> SnowprintId {
+        SnowprintId::from(self.reveal(obfuscated))
+    }
+}
+
+// never print round keys
+impl fmt::Debug for Obfuscator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Obfuscator")
+    }
+}
+
+fn round(half: u64, round_key: u64) -> u64 {
+    mix(half ^ round_key) & HALF_BIT_MASK
+}
@@ -0,0 +1,174 @@
+// Remember the last issued ms across process lifetimes
+//     - the high water mark is stored ahead of the clock, before a snowprint past it is returned
+//     - persist stores the last ms composed so a clean restart doesn't wait out the reserve
+//     - a new generator refuses, or waits, until its clock passes the stored mark
+//     - waiting follows the exhaustion policy in Settings
+
+use crate::exhaustion::wait_for_next_ms;
+use crate::{
+    get_initial_duration_ms, Clock, Error, ExhaustionPolicy, Settings, Snowprint, SnowprintId,
+    SystemClock,
+};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+// one fsync covers this many ms, a restart after a crash waits up to this long
+const RESERVE_MS: u64 = 1000;
+
+pub trait StateStore {
+    fn load_high_water_mark(&mut self) -> Result<Option<u64>, Error>;
+    fn store_high_water_mark(&mut self, duration_ms: u64) -> Result<(), Error>;
+}
+
+// writes to a temporary file, fsyncs, then renames over the previous mark
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FileStateStore {
+    path: PathBuf,
+}
+
+impl FileStateStore {
+    pub fn new(path: impl Into<PathBuf>) -> FileStateStore {
+        FileStateStore { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl StateStore for FileStateStore {
+    fn load_high_water_mark(&mut self) -> Result<Option<u64>, Error> {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(Error::FailedToLoadHighWaterMark { kind: err.kind() }),
+        };
+
+        match contents.trim().parse::<u64>() {
+            Ok(duration_ms) => Ok(Some(duration_ms)),
+            _ => Err(Error::FailedToLoadHighWaterMark {
+                kind: io::ErrorKind::InvalidData,
+            }),
+        }
+    }
+
+    fn store_high_water_mark(&mut self, duration_ms: u64) -> Result<(), Error> {
+        let mut tmp_path = self.path.clone().into_os_string();
+        tmp_path.push(".tmp");
+
+        let written = File::create(&tmp_path)
+            .and_then(|mut file| {
+                file.write_all(duration_ms.to_string().as_bytes())?;
+                file.sync_all()
+            })
+            .and_then(|_| fs::rename(&tmp_path, &self.path))
+            .and_then(|_| sync_parent_dir(&self.path));
+
+        match written {
+            Ok(_) => Ok(()),
+            Err(err) => Err(Error::FailedToStoreHighWaterMark { kind: err.kind() }),
+        }
+    }
+}
+
+fn sync_parent_dir(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => File::open(parent)?.sync_all(),
+        _ => Ok(()),
+    }
+}
+
+#[derive(Debug)]
+pub struct PersistentSnowprint<S: StateStore, C: Clock = SystemClock> {
+    snowprint: Snowprint<C>,
+    store: S,
+    high_water_mark: Option<u64>,
+    last_duration_ms: Option<u64>,
+}
+
+impl<S: StateStore> PersistentSnowprint<S> {
+    pub fn new(settings: Settings, store: S) -> Result<PersistentSnowprint<S>, Error> {
+        PersistentSnowprint::with_clock(settings, store, SystemClock)
+    }
+}
+
+impl<S: StateStore, C: Clock> PersistentSnowprint<S, C> {
+    pub fn with_clock(
+        settings: Settings,
+        mut store: S,
+        clock: C,
+    ) -> Result<PersistentSnowprint<S, C>, Error> {
+        let high_water_mark = store.load_high_water_mark()?;
+        if let Some(high_water_mark) = high_water_mark {
+            wait_for_high_water_mark(&settings, &clock, high_water_mark)?;
+        }
+
+        Ok(PersistentSnowprint {
+            snowprint: Snowprint::with_clock(settings, clock)?,
+            store,
+            high_water_mark,
+            last_duration_ms: None,
+        })
+    }
+
+    pub fn compose(&mut self) -> Result<SnowprintId, Error> {
+        let snowprint = self.snowprint.compose()?;
+
+        let duration_ms = snowprint.timestamp_ms();
+        if self.high_water_mark < Some(duration_ms) {
+            let high_water_mark = duration_ms.saturating_add(RESERVE_MS);
+            self.store.store_high_water_mark(high_water_mark)?;
+            self.high_water_mark = Some(high_water_mark);
+        }
+        self.last_duration_ms = Some(duration_ms);
+
+        Ok(snowprint)
+    }
+
+    // stores the last ms composed in place of the reserve, call it when shutting down
+    pub fn persist(&mut self) -> Result<(), Error> {
+        match self.last_duration_ms {
+            Some(duration_ms) if Some(duration_ms) < self.high_water_mark => {
+                self.store.store_high_water_mark(duration_ms)?;
+                self.high_water_mark = Some(duration_ms);
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn high_water_mark(&self) -> Option<u64> {
+        self.high_water_mark
+    }
+
+    pub fn into_store(self) -> S {
+        self.store
+    }
+}
+
+// snowprints up to and including the high water mark may already exist
+fn wait_for_high_water_mark(
+    settings: &Settings,
+    clock: &impl Clock,
+    high_water_mark: u64,
+) -> Result<(), Error> {
+    loop {
+        let duration_ms = get_initial_duration_ms(clock, settings.origin_system_time)?;
+        if high_water_mark < duration_ms {
+            return Ok(());
+        }
+        if settings.exhaustion_policy == ExhaustionPolicy::ReturnError {
+            return Err(Error::ClockBehindHighWaterMark {
+                duration_ms,
+                high_water_mark,
+            });
+        }
+        wait_for_next_ms(
+            clock,
+            settings.origin_system_time,
+            high_water_mark,
+            settings.exhaustion_policy,
+        );
+    }
+}
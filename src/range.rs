@@ -0,0 +1,71 @@
+// A compact run of reserved snowprints from a single ms
+//     - walks sequences first, then moves to the next logical volume
+//     - follows the same rotation as Snowprint::compose
+
+use crate::{
+    compose, get_logical_volume, get_logical_volume_length, Settings, SnowprintId, MAX_SEQUENCES,
+};
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SnowprintRange {
+    settings: Settings,
+    duration_ms: u64,
+    logical_volume_length: u64,
+    logical_volume: u64,
+    sequence: u64,
+    remaining: usize,
+}
+
+impl SnowprintRange {
+    pub(crate) fn new(
+        settings: &Settings,
+        duration_ms: u64,
+        logical_volume: u64,
+        sequence: u64,
+        remaining: usize,
+    ) -> SnowprintRange {
+        SnowprintRange {
+            settings: settings.clone(),
+            duration_ms,
+            logical_volume_length: get_logical_volume_length(settings),
+            logical_volume,
+            sequence,
+            remaining,
+        }
+    }
+
+    pub fn timestamp_ms(&self) -> u64 {
+        self.duration_ms
+    }
+}
+
+impl Iterator for SnowprintRange {
+    type Item = SnowprintId;
+
+    fn next(&mut self) -> Option<SnowprintId> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let snowprint = compose(
+            self.duration_ms,
+            get_logical_volume(&self.settings, self.logical_volume),
+            self.sequence,
+        );
+
+        self.remaining -= 1;
+        self.sequence += 1;
+        if self.sequence == MAX_SEQUENCES {
+            self.sequence = 0;
+            self.logical_volume = (self.logical_volume + 1) % self.logical_volume_length;
+        }
+
+        Some(SnowprintId::from(snowprint))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for SnowprintRange {}
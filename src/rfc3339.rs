@@ -0,0 +1,175 @@
+// Minimal RFC 3339 timestamps for origin_system_time and the command line
+//     - formats as UTC with as many fractional digits as needed to round trip
+//     - parses any UTC offset, fractional seconds up to nanoseconds
+
+// Calendar math from http://howardhinnant.github.io/date_algorithms.html
+
+#[cfg(test)]
+mod test;
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+pub fn format(system_time: SystemTime) -> String {
+    let (secs, nanos) = match system_time.duration_since(UNIX_EPOCH) {
+        Ok(duration) => (duration.as_secs() as i64, duration.subsec_nanos()),
+        Err(err) => {
+            let duration = err.duration();
+            match duration.subsec_nanos() {
+                0 => (-(duration.as_secs() as i64), 0),
+                nanos => (-(duration.as_secs() as i64) - 1, 1_000_000_000 - nanos),
+            }
+        }
+    };
+
+    let days = secs.div_euclid(SECONDS_PER_DAY);
+    let secs_of_day = secs.rem_euclid(SECONDS_PER_DAY);
+    let (year, month, day) = civil_from_days(days);
+
+    let fraction = match nanos {
+        0 => String::new(),
+        nanos if nanos % 1_000_000 == 0 => format!(".{:03}", nanos / 1_000_000),
+        nanos if nanos % 1_000 == 0 => format!(".{:06}", nanos / 1_000),
+        nanos => format!(".{:09}", nanos),
+    };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}{}Z",
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60,
+        fraction
+    )
+}
+
+pub fn parse(timestamp: &str) -> Option<SystemTime> {
+    let bytes = timestamp.as_bytes();
+    if bytes.len() < 20 {
+        return None;
+    }
+    if bytes[4] != b'-' || bytes[7] != b'-' || bytes[13] != b':' || bytes[16] != b':' {
+        return None;
+    }
+    if !matches!(bytes[10], b'T' | b't' | b' ') {
+        return None;
+    }
+
+    let year = parse_digits(&bytes[0..4])?;
+    let month = parse_digits(&bytes[5..7])?;
+    let day = parse_digits(&bytes[8..10])?;
+    let hour = parse_digits(&bytes[11..13])?;
+    let minute = parse_digits(&bytes[14..16])?;
+    let second = parse_digits(&bytes[17..19])?;
+    if !(1..=12).contains(&month) || day < 1 || days_in_month(year, month) < day {
+        return None;
+    }
+    if 23 < hour || 59 < minute || 59 < second {
+        return None;
+    }
+
+    // fractional seconds
+    let mut index = 19;
+    let mut nanos = 0;
+    if bytes[index] == b'.' {
+        index += 1;
+        let start = index;
+        while index < bytes.len() && bytes[index].is_ascii_digit() {
+            index += 1;
+        }
+        let digits = &bytes[start..index];
+        if digits.is_empty() || 9 < digits.len() {
+            return None;
+        }
+        nanos = parse_digits(digits)? * 10_i64.pow(9 - digits.len() as u32);
+    }
+
+    // offset from UTC
+    let offset_secs = match &bytes[index..] {
+        [b'Z' | b'z'] => 0,
+        [sign @ (b'+' | b'-'), hours @ .., b':', m1, m2] if hours.len() == 2 => {
+            let offset_hours = parse_digits(hours)?;
+            let offset_minutes = parse_digits(&[*m1, *m2])?;
+            if 23 < offset_hours || 59 < offset_minutes {
+                return None;
+            }
+            let offset_secs = offset_hours * 3600 + offset_minutes * 60;
+            match sign {
+                b'+' => offset_secs,
+                _ => -offset_secs,
+            }
+        }
+        _ => return None,
+    };
+
+    let secs =
+        days_from_civil(year, month, day) * SECONDS_PER_DAY + hour * 3600 + minute * 60 + second
+            - offset_secs;
+
+    match secs < 0 {
+        false => Some(UNIX_EPOCH + Duration::new(secs as u64, nanos as u32)),
+        true => {
+            let before_epoch = Duration::new(secs.unsigned_abs(), 0);
+            Some(UNIX_EPOCH - before_epoch + Duration::from_nanos(nanos as u64))
+        }
+    }
+}
+
+fn parse_digits(digits: &[u8]) -> Option<i64> {
+    let mut value = 0;
+    for digit in digits {
+        if !digit.is_ascii_digit() {
+            return None;
+        }
+        value = value * 10 + (digit - b'0') as i64;
+    }
+
+    Some(value)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = match month <= 2 {
+        true => year - 1,
+        _ => year,
+    };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month_from_march = (month + 9) % 12;
+    let day_of_year = (153 * month_from_march + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = match month_from_march < 10 {
+        true => month_from_march + 3,
+        _ => month_from_march - 9,
+    };
+    let year = year_of_era + era * 400;
+
+    match month <= 2 {
+        true => (year + 1, month, day),
+        _ => (year, month, day),
+    }
+}
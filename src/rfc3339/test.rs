@@ -0,0 +1,79 @@
+use super::*;
+
+const JANUARY_1ST_2024_AS_MS: u64 = 1704096000000;
+
+#[test]
+fn test_days_from_civil_and_back() {
+    assert_eq!(days_from_civil(1970, 1, 1), 0);
+    assert_eq!(days_from_civil(2000, 3, 1), 11_017);
+    assert_eq!(days_from_civil(1969, 12, 31), -1);
+
+    for days in [-719_468, -1, 0, 11_016, 11_017, 19_723, 2_932_896] {
+        let (year, month, day) = civil_from_days(days);
+        assert_eq!(days_from_civil(year, month, day), days);
+    }
+}
+
+#[test]
+fn test_format() {
+    let system_time = UNIX_EPOCH + Duration::from_millis(JANUARY_1ST_2024_AS_MS);
+    assert_eq!(format(system_time), "2024-01-01T08:00:00Z");
+
+    let system_time = system_time + Duration::from_millis(7);
+    assert_eq!(format(system_time), "2024-01-01T08:00:00.007Z");
+
+    let system_time = system_time + Duration::from_nanos(1);
+    assert_eq!(format(system_time), "2024-01-01T08:00:00.007000001Z");
+
+    assert_eq!(format(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+
+    let system_time = UNIX_EPOCH - Duration::from_millis(1500);
+    assert_eq!(format(system_time), "1969-12-31T23:59:58.500Z");
+}
+
+#[test]
+fn test_parse() {
+    let expected = UNIX_EPOCH + Duration::from_millis(JANUARY_1ST_2024_AS_MS);
+    assert_eq!(parse("2024-01-01T08:00:00Z"), Some(expected));
+    assert_eq!(parse("2024-01-01t08:00:00z"), Some(expected));
+    assert_eq!(parse("2024-01-01 08:00:00Z"), Some(expected));
+    assert_eq!(parse("2024-01-01T00:00:00-08:00"), Some(expected));
+    assert_eq!(parse("2024-01-01T09:30:00+01:30"), Some(expected));
+
+    let expected = expected + Duration::from_millis(250);
+    assert_eq!(parse("2024-01-01T08:00:00.25Z"), Some(expected));
+
+    let expected = UNIX_EPOCH - Duration::from_millis(1500);
+    assert_eq!(parse("1969-12-31T23:59:58.500Z"), Some(expected));
+}
+
+#[test]
+fn test_parse_rejects_invalid_timestamps() {
+    for timestamp in [
+        "",
+        "2024-01-01",
+        "2024-01-01T08:00:00",
+        "2024-13-01T08:00:00Z",
+        "2023-02-29T08:00:00Z",
+        "2024-01-01T24:00:00Z",
+        "2024-01-01T08:00:00.Z",
+        "2024-01-01T08:00:00.1234567890Z",
+        "2024-01-01T08:00:00+0800",
+        "2024/01/01T08:00:00Z",
+        "20a4-01-01T08:00:00Z",
+    ] {
+        assert_eq!(parse(timestamp), None, "{}", timestamp);
+    }
+
+    assert!(parse("2024-02-29T08:00:00Z").is_some());
+}
+
+#[test]
+fn test_format_and_parse_round_trip() {
+    for nanos in [0, 1, 999, 1_000, 1_000_000, 123_456_789] {
+        let system_time = UNIX_EPOCH
+            + Duration::from_millis(JANUARY_1ST_2024_AS_MS)
+            + Duration::from_nanos(nanos);
+        assert_eq!(parse(&format(system_time)), Some(system_time));
+    }
+}
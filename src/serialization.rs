@@ -0,0 +1,97 @@
+// Serde support behind the `serde` feature
+//     - SnowprintId serializes as a number, id_as_string keeps JavaScript from losing precision
+//     - origin_system_time serializes as RFC 3339 and deserializes from RFC 3339 or epoch ms
+
+use crate::{rfc3339, SnowprintId};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+impl Serialize for SnowprintId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.as_u64())
+    }
+}
+
+impl<'de> Deserialize<'de> for SnowprintId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<SnowprintId, D::Error> {
+        u64::deserialize(deserializer).map(SnowprintId::from)
+    }
+}
+
+// #[serde(with = "snowprints::serialization::id_as_string")]
+pub mod id_as_string {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        snowprint_id: &SnowprintId,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&snowprint_id.as_u64())
+    }
+
+    // numbers are accepted too so clients can migrate
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<SnowprintId, D::Error> {
+        deserializer.deserialize_any(SnowprintIdVisitor)
+    }
+
+    struct SnowprintIdVisitor;
+
+    impl de::Visitor<'_> for SnowprintIdVisitor {
+        type Value = SnowprintId;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a snowprint as a decimal string or number")
+        }
+
+        fn visit_u64<E: de::Error>(self, snowprint: u64) -> Result<SnowprintId, E> {
+            Ok(SnowprintId::from(snowprint))
+        }
+
+        fn visit_str<E: de::Error>(self, snowprint: &str) -> Result<SnowprintId, E> {
+            match snowprint.parse::<u64>() {
+                Ok(snowprint) => Ok(SnowprintId::from(snowprint)),
+                _ => Err(E::invalid_value(de::Unexpected::Str(snowprint), &self)),
+            }
+        }
+    }
+}
+
+// #[serde(with = "snowprints::serialization::origin_system_time")]
+pub mod origin_system_time {
+    use super::*;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    pub fn serialize<S: Serializer>(
+        origin_system_time: &SystemTime,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&rfc3339::format(*origin_system_time))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<SystemTime, D::Error> {
+        deserializer.deserialize_any(OriginSystemTimeVisitor)
+    }
+
+    struct OriginSystemTimeVisitor;
+
+    impl de::Visitor<'_> for OriginSystemTimeVisitor {
+        type Value = SystemTime;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("an RFC 3339 timestamp or milliseconds since the unix epoch")
+        }
+
+        fn visit_u64<E: de::Error>(self, epoch_ms: u64) -> Result<SystemTime, E> {
+            Ok(UNIX_EPOCH + Duration::from_millis(epoch_ms))
+        }
+
+        fn visit_str<E: de::Error>(self, timestamp: &str) -> Result<SystemTime, E> {
+            match rfc3339::parse(timestamp) {
+                Some(system_time) => Ok(system_time),
+                _ => Err(E::invalid_value(de::Unexpected::Str(timestamp), &self)),
+            }
+        }
+    }
+}
@@ -0,0 +1,341 @@
+// A small HTTP/1.1 service that owns one generator, behind the `server` feature
+//     - GET /id, GET /ids?count=n, and GET /decode/{id} answer with json
+//     - ids are json strings so clients without 64 bit integers keep every digit
+//     - each connection is read on its own thread, only composing waits on the generator
+//     - each connection closes after one response
+//     - shutting down stops accepting connections, finishes open ones, then persists the high water mark
+
+use crate::{rfc3339, Error, FileStateStore, PersistentSnowprint, Settings, SnowprintId};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime};
+
+const MAX_REQUEST_LEN: u64 = 16 * 1024;
+const MAX_HEADERS: usize = 100;
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+const ACCEPT_INTERVAL: Duration = Duration::from_millis(10);
+const MAX_CONNECTIONS: usize = 64;
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    pub address: String,
+    pub high_water_mark_path: PathBuf,
+    pub max_count: usize,
+    pub settings: Settings,
+}
+
+impl Default for ServerConfig {
+    fn default() -> ServerConfig {
+        ServerConfig {
+            address: "127.0.0.1:7878".to_string(),
+            high_water_mark_path: PathBuf::from("snowprints.high-water-mark"),
+            max_count: 1000,
+            settings: Settings::default(),
+        }
+    }
+}
+
+impl ServerConfig {
+    pub fn from_file(path: impl AsRef<Path>) -> io::Result<ServerConfig> {
+        let contents = fs::read_to_string(path)?;
+        serde_json::from_str(&contents)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+    }
+}
+
+// clones share the same flag
+#[derive(Debug, Clone, Default)]
+pub struct ShutdownHandle {
+    is_shutdown: Arc<AtomicBool>,
+}
+
+impl ShutdownHandle {
+    pub fn shutdown(&self) {
+        self.is_shutdown.store(true, Ordering::Release);
+    }
+
+    pub fn is_shutdown(&self) -> bool {
+        self.is_shutdown.load(Ordering::Acquire)
+    }
+}
+
+#[derive(Debug)]
+pub struct Server {
+    listener: TcpListener,
+    responder: Arc<Responder>,
+    shutdown_handle: ShutdownHandle,
+}
+
+impl Server {
+    pub fn bind(config: ServerConfig) -> io::Result<Server> {
+        let origin_system_time = config.settings.origin_system_time;
+        let snowprinter = PersistentSnowprint::new(
+            config.settings,
+            FileStateStore::new(config.high_water_mark_path),
+        )
+        .map_err(into_io_error)?;
+        let listener = TcpListener::bind(&config.address)?;
+        listener.set_nonblocking(true)?;
+
+        Ok(Server {
+            listener,
+            responder: Arc::new(Responder {
+                snowprinter: Mutex::new(snowprinter),
+                origin_system_time,
+                max_count: config.max_count,
+            }),
+            shutdown_handle: ShutdownHandle::default(),
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        self.shutdown_handle.clone()
+    }
+
+    // serves until the shutdown handle is triggered
+    //     - a slow client holds its own thread, past MAX_CONNECTIONS new ones wait in the backlog
+    pub fn run(self) -> io::Result<()> {
+        let mut connections: Vec<JoinHandle<()>> = Vec::new();
+        while !self.shutdown_handle.is_shutdown() {
+            connections.retain(|connection| !connection.is_finished());
+            if MAX_CONNECTIONS <= connections.len() {
+                thread::sleep(ACCEPT_INTERVAL);
+                continue;
+            }
+
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    let responder = Arc::clone(&self.responder);
+                    connections.push(thread::spawn(move || {
+                        // a misbehaving client only loses its own response
+                        let _ = responder.serve(stream);
+                    }));
+                }
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                    thread::sleep(ACCEPT_INTERVAL)
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+
+        for connection in connections {
+            let _ = connection.join();
+        }
+        self.responder.lock().persist().map_err(into_io_error)
+    }
+}
+
+// shared by every connection thread
+#[derive(Debug)]
+struct Responder {
+    snowprinter: Mutex<PersistentSnowprint<FileStateStore>>,
+    origin_system_time: SystemTime,
+    max_count: usize,
+}
+
+impl Responder {
+    fn lock(&self) -> MutexGuard<'_, PersistentSnowprint<FileStateStore>> {
+        self.snowprinter
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+    }
+
+    fn serve(&self, stream: TcpStream) -> io::Result<()> {
+        stream.set_nonblocking(false)?;
+        stream.set_read_timeout(Some(READ_TIMEOUT))?;
+
+        let response = match read_request(&stream)? {
+            Some((method, target)) => self.respond(&method, &target),
+            _ => Response::error(400, "malformed request"),
+        };
+        response.write_to(&stream)
+    }
+
+    fn respond(&self, method: &str, target: &str) -> Response {
+        if method != "GET" {
+            return Response::error(405, "only GET is supported");
+        }
+
+        let (path, query) = match target.split_once('?') {
+            Some((path, query)) => (path, query),
+            _ => (target, ""),
+        };
+        match path {
+            "/id" => match self.lock().compose() {
+                Ok(snowprint) => Response::json(200, &IdBody { id: snowprint }),
+                Err(err) => Response::from_error(err),
+            },
+            "/ids" => {
+                let count = match get_count(query, self.max_count) {
+                    Ok(count) => count,
+                    Err(message) => return Response::error(400, &message),
+                };
+                let mut snowprinter = self.lock();
+                let mut ids = Vec::with_capacity(count);
+                for _ in 0..count {
+                    match snowprinter.compose() {
+                        Ok(snowprint) => ids.push(IdBody { id: snowprint }),
+                        Err(err) => return Response::from_error(err),
+                    }
+                }
+                Response::json(200, &IdsBody { ids })
+            }
+            path => match path.strip_prefix("/decode/") {
+                Some(id) => match parse_id(id) {
+                    Some(snowprint) => {
+                        Response::json(200, &DecodeBody::new(snowprint, self.origin_system_time))
+                    }
+                    _ => Response::error(400, "id is not a decimal or base32 snowprint"),
+                },
+                _ => Response::error(404, "not found"),
+            },
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct IdBody {
+    #[serde(with = "crate::serialization::id_as_string")]
+    id: SnowprintId,
+}
+
+#[derive(Serialize)]
+struct IdsBody {
+    ids: Vec<IdBody>,
+}
+
+#[derive(Serialize)]
+struct DecodeBody {
+    #[serde(with = "crate::serialization::id_as_string")]
+    id: SnowprintId,
+    created_at: String,
+    timestamp_ms: u64,
+    logical_volume: u64,
+    sequence: u64,
+}
+
+impl DecodeBody {
+    fn new(snowprint: SnowprintId, origin_system_time: SystemTime) -> DecodeBody {
+        DecodeBody {
+            id: snowprint,
+            created_at: rfc3339::format(snowprint.to_system_time(origin_system_time)),
+            timestamp_ms: snowprint.timestamp_ms(),
+            logical_volume: snowprint.logical_volume(),
+            sequence: snowprint.sequence(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorBody<'a> {
+    error: &'a str,
+}
+
+struct Response {
+    status: u16,
+    body: String,
+}
+
+impl Response {
+    fn json(status: u16, body: &impl Serialize) -> Response {
+        match serde_json::to_string(body) {
+            Ok(body) => Response { status, body },
+            Err(_) => Response::error(500, "failed to serialize response"),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Response {
+        Response {
+            status,
+            body: serde_json::to_string(&ErrorBody { error: message }).unwrap_or_default(),
+        }
+    }
+
+    // exhaustion and clock trouble pass with time, everything else is the server's fault
+    fn from_error(err: Error) -> Response {
+        let status = match err {
+            Error::ExceededAvailableSequences
+            | Error::ClockMovedBackwards { .. }
+            | Error::ClockBehindHighWaterMark { .. } => 503,
+            _ => 500,
+        };
+        Response::error(status, &err.to_string())
+    }
+
+    fn write_to(&self, mut stream: &TcpStream) -> io::Result<()> {
+        let reason = match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            503 => "Service Unavailable",
+            _ => "Internal Server Error",
+        };
+        write!(
+            stream,
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.status,
+            reason,
+            self.body.len(),
+            self.body
+        )?;
+        stream.flush()
+    }
+}
+
+// returns None for anything that isn't an HTTP/1.x request line followed by headers
+fn read_request(stream: &TcpStream) -> io::Result<Option<(String, String)>> {
+    let mut reader = BufReader::new(stream).take(MAX_REQUEST_LEN);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    // requests have no body we care about, but headers must be drained before replying
+    for _ in 0..MAX_HEADERS {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim_end().is_empty() {
+            break;
+        }
+    }
+
+    let mut parts = request_line.split_whitespace();
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(method), Some(target), Some(version)) if version.starts_with("HTTP/1.") => {
+            Ok(Some((method.to_string(), target.to_string())))
+        }
+        _ => Ok(None),
+    }
+}
+
+fn get_count(query: &str, max_count: usize) -> Result<usize, String> {
+    let count = query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("count="))
+        .unwrap_or("1");
+    match count.parse::<usize>() {
+        Ok(count) if 0 < count && count <= max_count => Ok(count),
+        _ => Err(format!("count must be between 1 and {}", max_count)),
+    }
+}
+
+fn parse_id(id: &str) -> Option<SnowprintId> {
+    match id.parse::<u64>() {
+        Ok(snowprint) => Some(SnowprintId::from(snowprint)),
+        _ => id.parse::<SnowprintId>().ok(),
+    }
+}
+
+fn into_io_error(err: Error) -> io::Error {
+    io::Error::other(err)
+}
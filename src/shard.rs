@@ -0,0 +1,264 @@
+// Route snowprints to physical shards by logical volume
+//     - a ShardMap assigns ranges of logical volumes to named shards
+//     - ranges are kept sorted, non-overlapping, and merged with neighbors on the same shard
+//     - rebalancing is planned by editing a copy and diffing it against the original
+
+// https://instagram-engineering.com/sharding-ids-at-instagram-1cf5a71e5a5c
+
+use crate::{Error, SnowprintId, MAX_LOGICAL_VOLUMES};
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ShardRange {
+    pub logical_volume_base: u64,
+    pub logical_volume_length: u64,
+    pub shard: String,
+}
+
+// a range of logical volumes that changes shards, None means unassigned
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ShardMove {
+    pub logical_volume_base: u64,
+    pub logical_volume_length: u64,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(try_from = "Vec<ShardRange>", into = "Vec<ShardRange>")
+)]
+pub struct ShardMap {
+    ranges: Vec<ShardRange>,
+}
+
+impl ShardMap {
+    pub fn new() -> ShardMap {
+        ShardMap::default()
+    }
+
+    // splits every logical volume across shards as evenly as possible
+    pub fn uniform(shards: &[&str]) -> Result<ShardMap, Error> {
+        let shard_count = shards.len() as u64;
+        if MAX_LOGICAL_VOLUMES < shard_count {
+            return Err(Error::ExceededAvailableLogicalVolumes {
+                logical_volume_base: 0,
+                logical_volume_length: shard_count,
+            });
+        }
+
+        let mut shard_map = ShardMap::new();
+        let mut logical_volume_base = 0;
+        for (index, shard) in shards.iter().enumerate() {
+            let logical_volume_end = MAX_LOGICAL_VOLUMES * (index as u64 + 1) / shard_count;
+            shard_map.assign(
+                logical_volume_base,
+                logical_volume_end - logical_volume_base,
+                *shard,
+            )?;
+            logical_volume_base = logical_volume_end;
+        }
+
+        Ok(shard_map)
+    }
+
+    // replaces whatever the logical volumes were assigned to before
+    pub fn assign(
+        &mut self,
+        logical_volume_base: u64,
+        logical_volume_length: u64,
+        shard: impl Into<String>,
+    ) -> Result<(), Error> {
+        self.reassign(
+            logical_volume_base,
+            logical_volume_length,
+            Some(shard.into()),
+        )
+    }
+
+    pub fn unassign(
+        &mut self,
+        logical_volume_base: u64,
+        logical_volume_length: u64,
+    ) -> Result<(), Error> {
+        self.reassign(logical_volume_base, logical_volume_length, None)
+    }
+
+    pub fn shard_for_logical_volume(&self, logical_volume: u64) -> Option<&str> {
+        let index = self
+            .ranges
+            .partition_point(|range| range.logical_volume_base <= logical_volume);
+        match index {
+            0 => None,
+            index => {
+                let range = &self.ranges[index - 1];
+                match logical_volume < range.logical_volume_base + range.logical_volume_length {
+                    true => Some(&range.shard),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    pub fn shard_for(&self, snowprint_id: SnowprintId) -> Option<&str> {
+        self.shard_for_logical_volume(snowprint_id.logical_volume())
+    }
+
+    pub fn ranges(&self) -> &[ShardRange] {
+        &self.ranges
+    }
+
+    fn reassign(
+        &mut self,
+        logical_volume_base: u64,
+        logical_volume_length: u64,
+        shard: Option<String>,
+    ) -> Result<(), Error> {
+        let logical_volume_end = check_range(logical_volume_base, logical_volume_length)?;
+
+        let mut ranges = Vec::with_capacity(self.ranges.len() + 2);
+        for range in self.ranges.drain(..) {
+            let range_end = range.logical_volume_base + range.logical_volume_length;
+            if range.logical_volume_base < logical_volume_base {
+                ranges.push(ShardRange {
+                    logical_volume_base: range.logical_volume_base,
+                    logical_volume_length: range_end.min(logical_volume_base)
+                        - range.logical_volume_base,
+                    shard: range.shard.clone(),
+                });
+            }
+            if logical_volume_end < range_end {
+                let remainder_base = range.logical_volume_base.max(logical_volume_end);
+                ranges.push(ShardRange {
+                    logical_volume_base: remainder_base,
+                    logical_volume_length: range_end - remainder_base,
+                    shard: range.shard,
+                });
+            }
+        }
+        if let Some(shard) = shard {
+            ranges.push(ShardRange {
+                logical_volume_base,
+                logical_volume_length,
+                shard,
+            });
+        }
+        ranges.sort_by_key(|range| range.logical_volume_base);
+
+        self.ranges = merge_neighbors(ranges);
+        Ok(())
+    }
+
+    // every range of logical volumes that would change shards going from self to target
+    pub fn diff(&self, target: &ShardMap) -> Vec<ShardMove> {
+        let mut boundaries: Vec<u64> = self
+            .ranges
+            .iter()
+            .chain(target.ranges.iter())
+            .flat_map(|range| {
+                [
+                    range.logical_volume_base,
+                    range.logical_volume_base + range.logical_volume_length,
+                ]
+            })
+            .collect();
+        boundaries.sort_unstable();
+        boundaries.dedup();
+
+        let mut moves: Vec<ShardMove> = Vec::new();
+        for window in boundaries.windows(2) {
+            let (logical_volume_base, logical_volume_end) = (window[0], window[1]);
+            let from = self.shard_for_logical_volume(logical_volume_base);
+            let to = target.shard_for_logical_volume(logical_volume_base);
+            if from == to {
+                continue;
+            }
+
+            if let Some(prev) = moves.last_mut() {
+                if prev.logical_volume_base + prev.logical_volume_length == logical_volume_base
+                    && prev.from.as_deref() == from
+                    && prev.to.as_deref() == to
+                {
+                    prev.logical_volume_length += logical_volume_end - logical_volume_base;
+                    continue;
+                }
+            }
+            moves.push(ShardMove {
+                logical_volume_base,
+                logical_volume_length: logical_volume_end - logical_volume_base,
+                from: from.map(String::from),
+                to: to.map(String::from),
+            });
+        }
+
+        moves
+    }
+}
+
+// ranges may arrive in any order but must not overlap
+impl TryFrom<Vec<ShardRange>> for ShardMap {
+    type Error = Error;
+
+    fn try_from(ranges: Vec<ShardRange>) -> Result<ShardMap, Error> {
+        let mut ranges = ranges;
+        for range in &ranges {
+            check_range(range.logical_volume_base, range.logical_volume_length)?;
+        }
+        ranges.sort_by_key(|range| range.logical_volume_base);
+        for pair in ranges.windows(2) {
+            if pair[1].logical_volume_base
+                < pair[0].logical_volume_base + pair[0].logical_volume_length
+            {
+                return Err(Error::OverlappingShardRanges {
+                    logical_volume: pair[1].logical_volume_base,
+                });
+            }
+        }
+
+        Ok(ShardMap {
+            ranges: merge_neighbors(ranges),
+        })
+    }
+}
+
+impl From<ShardMap> for Vec<ShardRange> {
+    fn from(shard_map: ShardMap) -> Vec<ShardRange> {
+        shard_map.ranges
+    }
+}
+
+fn check_range(logical_volume_base: u64, logical_volume_length: u64) -> Result<u64, Error> {
+    if logical_volume_length == 0 {
+        return Err(Error::LogicalVolumeModuloIsZero);
+    }
+    match logical_volume_base.checked_add(logical_volume_length) {
+        Some(logical_volume_end) if logical_volume_end <= MAX_LOGICAL_VOLUMES => {
+            Ok(logical_volume_end)
+        }
+        _ => Err(Error::ExceededAvailableLogicalVolumes {
+            logical_volume_base,
+            logical_volume_length,
+        }),
+    }
+}
+
+// expects sorted, non-overlapping ranges
+fn merge_neighbors(ranges: Vec<ShardRange>) -> Vec<ShardRange> {
+    let mut merged: Vec<ShardRange> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        if let Some(prev) = merged.last_mut() {
+            if prev.logical_volume_base + prev.logical_volume_length == range.logical_volume_base
+                && prev.shard == range.shard
+            {
+                prev.logical_volume_length += range.logical_volume_length;
+                continue;
+            }
+        }
+        merged.push(range);
+    }
+
+    merged
+}
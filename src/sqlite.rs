@@ -0,0 +1,202 @@
+// Volume leases kept in a sql table, behind the `sqlite` feature
+//     - acquiring runs in an immediate transaction so writers queue instead of racing
+//     - heartbeats and releases are single statements matched on holder and base
+//     - releases only pull expires_at_ms in, expired rows are deleted by the next acquire
+//     - sqlite integers are signed, unix ms and logical volumes fit well within i64
+
+use crate::lease::{acquire_from_leases, check_lease_request, get_expires_at_ms};
+use crate::{Error, VolumeLease, VolumeLeaseStore};
+use rusqlite::{params, Connection, TransactionBehavior};
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+const CREATE_TABLE: &str = "CREATE TABLE IF NOT EXISTS snowprints_volume_leases (
+    logical_volume_base INTEGER PRIMARY KEY,
+    logical_volume_length INTEGER NOT NULL,
+    holder TEXT NOT NULL,
+    expires_at_ms INTEGER NOT NULL
+)";
+
+#[derive(Debug)]
+pub struct SqliteLeaseStore {
+    connection: Connection,
+    logical_volume_base: u64,
+    logical_volume_length: u64,
+}
+
+impl SqliteLeaseStore {
+    // waits up to 5s for other writers, pass a connection to new for anything else
+    pub fn open(
+        path: impl AsRef<Path>,
+        logical_volume_base: u64,
+        logical_volume_length: u64,
+    ) -> Result<SqliteLeaseStore, Error> {
+        let connection = Connection::open(path)
+            .and_then(|connection| {
+                connection.busy_timeout(BUSY_TIMEOUT)?;
+                Ok(connection)
+            })
+            .map_err(into_lease_store_error)?;
+        SqliteLeaseStore::new(connection, logical_volume_base, logical_volume_length)
+    }
+
+    // creates the leases table if it doesn't exist yet
+    pub fn new(
+        connection: Connection,
+        logical_volume_base: u64,
+        logical_volume_length: u64,
+    ) -> Result<SqliteLeaseStore, Error> {
+        connection
+            .execute(CREATE_TABLE, [])
+            .map_err(into_lease_store_error)?;
+
+        Ok(SqliteLeaseStore {
+            connection,
+            logical_volume_base,
+            logical_volume_length,
+        })
+    }
+
+    pub fn into_connection(self) -> Connection {
+        self.connection
+    }
+}
+
+impl VolumeLeaseStore for SqliteLeaseStore {
+    fn acquire(
+        &mut self,
+        holder: &str,
+        logical_volume_length: u64,
+        ttl_ms: u64,
+        now_ms: u64,
+    ) -> Result<VolumeLease, Error> {
+        check_lease_request(
+            self.logical_volume_base,
+            self.logical_volume_length,
+            holder,
+            logical_volume_length,
+            ttl_ms,
+        )?;
+
+        let transaction = self
+            .connection
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(into_lease_store_error)?;
+        transaction
+            .execute(
+                "DELETE FROM snowprints_volume_leases WHERE expires_at_ms <= ?1",
+                params![now_ms as i64],
+            )
+            .map_err(into_lease_store_error)?;
+
+        let mut leases = transaction
+            .prepare(
+                "SELECT holder, logical_volume_base, logical_volume_length, expires_at_ms
+                FROM snowprints_volume_leases",
+            )
+            .and_then(|mut statement| {
+                statement
+                    .query_map([], |row| {
+                        Ok(VolumeLease {
+                            holder: row.get(0)?,
+                            logical_volume_base: row.get::<_, i64>(1)? as u64,
+                            logical_volume_length: row.get::<_, i64>(2)? as u64,
+                            expires_at_ms: row.get::<_, i64>(3)? as u64,
+                        })
+                    })?
+                    .collect::<Result<Vec<VolumeLease>, rusqlite::Error>>()
+            })
+            .map_err(into_lease_store_error)?;
+
+        let lease = acquire_from_leases(
+            &mut leases,
+            self.logical_volume_base,
+            self.logical_volume_length,
+            holder,
+            logical_volume_length,
+            ttl_ms,
+            now_ms,
+        )?;
+        transaction
+            .execute(
+                "INSERT INTO snowprints_volume_leases
+                (logical_volume_base, logical_volume_length, holder, expires_at_ms)
+                VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    lease.logical_volume_base as i64,
+                    lease.logical_volume_length as i64,
+                    lease.holder,
+                    lease.expires_at_ms as i64
+                ],
+            )
+            .and_then(|_| transaction.commit())
+            .map_err(into_lease_store_error)?;
+
+        Ok(lease)
+    }
+
+    fn heartbeat(
+        &mut self,
+        lease: &VolumeLease,
+        ttl_ms: u64,
+        now_ms: u64,
+    ) -> Result<VolumeLease, Error> {
+        let expires_at_ms = get_expires_at_ms(now_ms, ttl_ms)?;
+        let updated = self
+            .connection
+            .execute(
+                "UPDATE snowprints_volume_leases SET expires_at_ms = ?1
+                WHERE holder = ?2 AND logical_volume_base = ?3 AND logical_volume_length = ?4
+                AND ?5 < expires_at_ms",
+                params![
+                    expires_at_ms as i64,
+                    lease.holder,
+                    lease.logical_volume_base as i64,
+                    lease.logical_volume_length as i64,
+                    now_ms as i64
+                ],
+            )
+            .map_err(into_lease_store_error)?;
+
+        match updated {
+            0 => Err(Error::LeaseLapsed {
+                expires_at_ms: lease.expires_at_ms,
+            }),
+            _ => Ok(VolumeLease {
+                expires_at_ms,
+                ..lease.clone()
+            }),
+        }
+    }
+
+    fn release(&mut self, lease: &VolumeLease) -> Result<(), Error> {
+        self.connection
+            .execute(
+                "UPDATE snowprints_volume_leases SET expires_at_ms = MIN(expires_at_ms, ?1)
+                WHERE holder = ?2 AND logical_volume_base = ?3",
+                params![
+                    lease.expires_at_ms as i64,
+                    lease.holder,
+                    lease.logical_volume_base as i64
+                ],
+            )
+            .map_err(into_lease_store_error)?;
+
+        Ok(())
+    }
+}
+
+fn into_lease_store_error(err: rusqlite::Error) -> Error {
+    let kind = match err {
+        rusqlite::Error::SqliteFailure(failure, _)
+            if failure.code == rusqlite::ErrorCode::DatabaseBusy =>
+        {
+            io::ErrorKind::WouldBlock
+        }
+        _ => io::ErrorKind::Other,
+    };
+    Error::FailedToAccessLeaseStore { kind }
+}
@@ -0,0 +1,189 @@
+// A lock-free Snowprint that can be shared across threads
+//     - state is split across two atomics, both tagged with a duration
+//     - `current` packs duration, logical volume, and sequence like a snowprint
+//     - `prev` packs duration and the logical volume the previous ms ended on
+//     - `prev` is always advanced before `current` so it is never behind
+
+// Both atomics only ever move forward, so compare-and-swap cannot suffer ABA.
+// Each step decodes a `State` and runs it through compose_from_settings_and_state
+// so rotation and exhaustion match Snowprint exactly.
+
+#[cfg(test)]
+mod test;
+
+#[cfg(feature = "async")]
+use crate::exhaustion::get_nap_until_next_ms;
+use crate::exhaustion::wait_for_next_ms;
+use crate::{
+    check_settings, compose, compose_from_settings_and_state, decompose, get_initial_duration_ms,
+    get_initial_prev_logical_volume, get_most_recent_duration_ms, get_prev_logical_volume,
+    get_stop_logical_volume, Clock, Error, ExhaustionPolicy, Settings, SnowprintId, State,
+    SystemClock, LOGICAL_VOLUME_BIT_LEN,
+};
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "async")]
+use std::time::Duration;
+
+const PREV_LOGICAL_VOLUME_BIT_MASK: u64 = (1 << LOGICAL_VOLUME_BIT_LEN) - 1;
+
+#[derive(Debug)]
+struct AtomicState {
+    current: AtomicU64,
+    prev: AtomicU64,
+}
+
+#[derive(Debug)]
+pub struct SyncSnowprint<C: Clock = SystemClock> {
+    settings: Settings,
+    state: AtomicState,
+    clock: C,
+}
+
+impl SyncSnowprint {
+    pub fn new(settings: Settings) -> Result<SyncSnowprint, Error> {
+        SyncSnowprint::with_clock(settings, SystemClock)
+    }
+}
+
+impl<C: Clock> SyncSnowprint<C> {
+    pub fn with_clock(settings: Settings, clock: C) -> Result<SyncSnowprint<C>, Error> {
+        check_settings(&settings)?;
+
+        let duration_ms = get_initial_duration_ms(&clock, settings.origin_system_time)?;
+        let prev_logical_volume = get_initial_prev_logical_volume(&settings);
+
+        Ok(SyncSnowprint {
+            settings,
+            state: AtomicState {
+                current: AtomicU64::new(compose(duration_ms, 0, 0)),
+                prev: AtomicU64::new(pack_prev(duration_ms, prev_logical_volume)),
+            },
+            clock,
+        })
+    }
+
+    pub fn compose(&self) -> Result<SnowprintId, Error> {
+        self.compose_with_exhaustion_policy(self.settings.exhaustion_policy)
+    }
+
+    // waits for the next ms even when settings say to return an error
+    pub fn compose_blocking(&self) -> Result<SnowprintId, Error> {
+        let exhaustion_policy = match self.settings.exhaustion_policy {
+            ExhaustionPolicy::ReturnError => ExhaustionPolicy::Sleep,
+            exhaustion_policy => exhaustion_policy,
+        };
+        self.compose_with_exhaustion_policy(exhaustion_policy)
+    }
+
+    fn compose_with_exhaustion_policy(
+        &self,
+        exhaustion_policy: ExhaustionPolicy,
+    ) -> Result<SnowprintId, Error> {
+        loop {
+            let (duration_ms, snowprint) = self.compose_once();
+            match snowprint {
+                Err(Error::ExceededAvailableSequences)
+                    if exhaustion_policy != ExhaustionPolicy::ReturnError =>
+                {
+                    wait_for_next_ms(
+                        &self.clock,
+                        self.settings.origin_system_time,
+                        duration_ms,
+                        exhaustion_policy,
+                    );
+                }
+                snowprint => return snowprint.map(SnowprintId::from),
+            }
+        }
+    }
+
+    // returns the ms that was attempted alongside the result
+    pub(crate) fn compose_once(&self) -> (u64, Result<u64, Error>) {
+        let (prev_duration_ms, _) = unpack_prev(self.state.prev.load(Ordering::Acquire));
+        let duration_ms =
+            match get_most_recent_duration_ms(&self.clock, &self.settings, prev_duration_ms) {
+                Ok(duration_ms) => duration_ms,
+                Err(err) => return (prev_duration_ms, Err(err)),
+            };
+        let snowprint =
+            compose_from_settings_and_atomic_state(&self.settings, &self.state, duration_ms);
+        (duration_ms, snowprint)
+    }
+
+    #[cfg(feature = "async")]
+    pub(crate) fn get_nap_until_next_ms(&self, duration_ms: u64) -> Option<Duration> {
+        get_nap_until_next_ms(&self.clock, self.settings.origin_system_time, duration_ms)
+    }
+}
+
+fn pack_prev(duration_ms: u64, prev_logical_volume: u64) -> u64 {
+    duration_ms << LOGICAL_VOLUME_BIT_LEN | prev_logical_volume
+}
+
+fn unpack_prev(prev: u64) -> (u64, u64) {
+    (
+        prev >> LOGICAL_VOLUME_BIT_LEN,
+        prev & PREV_LOGICAL_VOLUME_BIT_MASK,
+    )
+}
+
+fn compose_from_settings_and_atomic_state(
+    settings: &Settings,
+    state: &AtomicState,
+    duration_ms: u64,
+) -> Result<u64, Error> {
+    loop {
+        // load `current` first so `prev` is at least as recent
+        let current = state.current.load(Ordering::Acquire);
+        let prev = state.prev.load(Ordering::Acquire);
+        let (current_duration_ms, logical_volume, sequence) = decompose(current);
+        let (mut prev_duration_ms, mut prev_logical_volume) = unpack_prev(prev);
+
+        // another thread may have already moved on to a later ms
+        let duration_ms = match prev_duration_ms < duration_ms {
+            true => duration_ms,
+            _ => prev_duration_ms,
+        };
+
+        // time changed, record the logical volume the last ms ended on
+        if prev_duration_ms < duration_ms {
+            let logical_volume = get_prev_logical_volume(settings, logical_volume, duration_ms);
+            let next_prev = pack_prev(duration_ms, logical_volume);
+            if state
+                .prev
+                .compare_exchange(prev, next_prev, Ordering::AcqRel, Ordering::Acquire)
+                .is_err()
+            {
+                continue;
+            }
+            prev_duration_ms = duration_ms;
+            prev_logical_volume = logical_volume;
+        }
+
+        // when `current` is behind `prev` the time change is still in flight,
+        // rotate from the recorded logical volume instead of the latest one
+        let mut next_state = State {
+            prev_duration_ms: current_duration_ms,
+            sequence,
+            logical_volume: match current_duration_ms < prev_duration_ms {
+                true => prev_logical_volume,
+                _ => logical_volume,
+            },
+            prev_logical_volume: get_stop_logical_volume(settings, prev_logical_volume),
+        };
+        let snowprint = compose_from_settings_and_state(settings, &mut next_state, duration_ms)?;
+
+        let next_current = compose(
+            next_state.prev_duration_ms,
+            next_state.logical_volume,
+            next_state.sequence,
+        );
+        if state
+            .current
+            .compare_exchange(current, next_current, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+        {
+            return Ok(snowprint);
+        }
+    }
+}
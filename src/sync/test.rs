@@ -0,0 +1,137 @@
+use super::*;
+use std::time::SystemTime;
+
+fn atomic_state_from_state(state: &State) -> AtomicState {
+    AtomicState {
+        current: AtomicU64::new(compose(
+            state.prev_duration_ms,
+            state.logical_volume,
+            state.sequence,
+        )),
+        prev: AtomicU64::new(pack_prev(state.prev_duration_ms, state.prev_logical_volume)),
+    }
+}
+
+#[test]
+fn test_pack_and_unpack_prev() {
+    let prev = pack_prev(987654321, 8191);
+    assert_eq!(unpack_prev(prev), (987654321, 8191));
+}
+
+#[test]
+fn test_atomic_state_matches_state() {
+    let settings = Settings {
+        origin_system_time: SystemTime::now(),
+        logical_volume_base: 1024,
+        logical_volume_length: 3,
+        ..Default::default()
+    };
+    let mut state = State {
+        prev_duration_ms: 0,
+        sequence: 0,
+        logical_volume: 0,
+        prev_logical_volume: 0,
+    };
+    let atomic_state = atomic_state_from_state(&state);
+
+    // walk through time changes, rollovers, and exhaustion
+    for duration_ms in [0, 0, 1, 1, 1, 5, 5, 4, 9] {
+        for _ in 0..2100 {
+            let expected = compose_from_settings_and_state(&settings, &mut state, duration_ms);
+            let snowprint =
+                compose_from_settings_and_atomic_state(&settings, &atomic_state, duration_ms);
+            assert_eq!(expected, snowprint);
+        }
+    }
+}
+
+#[test]
+fn test_atomic_state_matches_state_strictly_increasing() {
+    let settings = Settings {
+        origin_system_time: SystemTime::now(),
+        logical_volume_base: 1024,
+        logical_volume_length: 3,
+        strictly_increasing: true,
+        ..Default::default()
+    };
+    let mut state = State {
+        prev_duration_ms: 0,
+        sequence: 0,
+        logical_volume: 0,
+        prev_logical_volume: 0,
+    };
+    let atomic_state = atomic_state_from_state(&state);
+
+    // `prev` still records where each ms ended, only the stop moves to index 0
+    for duration_ms in [0, 0, 1, 1, 1, 5, 5, 4, 9] {
+        for _ in 0..2100 {
+            let expected = compose_from_settings_and_state(&settings, &mut state, duration_ms);
+            let snowprint =
+                compose_from_settings_and_atomic_state(&settings, &atomic_state, duration_ms);
+            assert_eq!(expected, snowprint);
+        }
+    }
+}
+
+#[test]
+fn test_compose_from_settings_and_atomic_state() {
+    let settings = Settings {
+        origin_system_time: SystemTime::now(),
+        logical_volume_base: 4096,
+        logical_volume_length: 4096,
+        ..Default::default()
+    };
+
+    // time did not change
+    let atomic_state = atomic_state_from_state(&State {
+        prev_duration_ms: 0,
+        sequence: 255,
+        logical_volume: 2048,
+        prev_logical_volume: 4095,
+    });
+    let snowprint = compose_from_settings_and_atomic_state(&settings, &atomic_state, 0);
+    assert_eq!(snowprint, Ok(compose(0, 6144, 256)));
+
+    // fail out
+    let atomic_state = atomic_state_from_state(&State {
+        prev_duration_ms: 0,
+        sequence: 1023,
+        logical_volume: 4095,
+        prev_logical_volume: 0,
+    });
+    let snowprint = compose_from_settings_and_atomic_state(&settings, &atomic_state, 0);
+    assert_eq!(snowprint, Err(Error::ExceededAvailableSequences));
+
+    // time changed
+    let snowprint = compose_from_settings_and_atomic_state(&settings, &atomic_state, 1);
+    assert_eq!(snowprint, Ok(compose(1, 4096, 0)));
+    assert_eq!(
+        unpack_prev(atomic_state.prev.load(Ordering::Acquire)),
+        (1, 4095)
+    );
+
+    // time went backwards, remain on the most recent ms
+    let snowprint = compose_from_settings_and_atomic_state(&settings, &atomic_state, 0);
+    assert_eq!(snowprint, Ok(compose(1, 4096, 1)));
+}
+
+#[test]
+fn test_compose_from_settings_and_atomic_state_time_change_in_flight() {
+    let settings = Settings {
+        origin_system_time: SystemTime::now(),
+        logical_volume_base: 0,
+        logical_volume_length: 8192,
+        ..Default::default()
+    };
+
+    // `prev` was advanced to ms 3 but `current` is still on ms 2
+    let atomic_state = AtomicState {
+        current: AtomicU64::new(compose(2, 10, 7)),
+        prev: AtomicU64::new(pack_prev(3, 9)),
+    };
+    let snowprint = compose_from_settings_and_atomic_state(&settings, &atomic_state, 2);
+    assert_eq!(snowprint, Ok(compose(3, 10, 0)));
+
+    let snowprint = compose_from_settings_and_atomic_state(&settings, &atomic_state, 3);
+    assert_eq!(snowprint, Ok(compose(3, 10, 1)));
+}
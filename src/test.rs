@@ -1,4 +1,5 @@
 use super::*;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, SystemTime};
 
 #[test]
@@ -7,6 +8,7 @@ fn test_check_failed_settings() {
         origin_system_time: SystemTime::now(),
         logical_volume_base: 4096,
         logical_volume_length: 0,
+        ..Default::default()
     };
     let snowprinter = Snowprint::new(mod_fail_settings);
     assert_eq!(snowprinter, Err(Error::LogicalVolumeModuloIsZero));
@@ -15,25 +17,111 @@ fn test_check_failed_settings() {
         origin_system_time: SystemTime::now(),
         logical_volume_base: 4096,
         logical_volume_length: 8192,
+        ..Default::default()
     };
     let snowprinter2 = Snowprint::new(exceed_fail_settings);
-    assert_eq!(snowprinter2, Err(Error::ExceededAvailableLogicalVolumes));
+    assert_eq!(
+        snowprinter2,
+        Err(Error::ExceededAvailableLogicalVolumes {
+            logical_volume_base: 4096,
+            logical_volume_length: 8192,
+        })
+    );
 }
 
 #[test]
 fn test_get_most_recent_duration_ms() {
-    let duration = SystemTime::now();
+    let origin = SystemTime::now();
+    let clock = ManualClock::new(origin);
+    let settings = Settings {
+        origin_system_time: origin,
+        ..Default::default()
+    };
+
+    let duration_ms = get_most_recent_duration_ms(&clock, &settings, 0);
+    assert_eq!(duration_ms, Ok(0));
+
+    // time moves forward
+    clock.advance(Duration::from_millis(5));
+    let greater_duration_ms = get_most_recent_duration_ms(&clock, &settings, 0);
+    assert_eq!(greater_duration_ms, Ok(5));
 
-    let duration_ms = get_most_recent_duration_ms(duration, 0);
-    assert_eq!(duration_ms, 0);
+    // time goes backwards
+    clock.rewind(Duration::from_millis(3));
+    let duration_ms = get_most_recent_duration_ms(&clock, &settings, 5);
+    assert_eq!(duration_ms, Ok(5));
 
-    let greater_duration = duration + Duration::from_millis(1);
-    let greater_duration_ms = get_most_recent_duration_ms(greater_duration, duration_ms);
+    // time goes backwards past the origin
+    clock.set(origin - Duration::from_millis(1));
+    let duration_ms = get_most_recent_duration_ms(&clock, &settings, 5);
+    assert_eq!(duration_ms, Ok(5));
+}
+
+#[test]
+fn test_get_most_recent_duration_ms_with_max_backward_skew() {
+    let origin = SystemTime::now();
+    let clock = ManualClock::new(origin + Duration::from_millis(90));
+    let skews = Arc::new(Mutex::new(Vec::new()));
+    let settings = Settings {
+        origin_system_time: origin,
+        max_backward_skew_ms: Some(10),
+        clock_skew_hook: Some(ClockSkewHook::new({
+            let skews = skews.clone();
+            move |by_ms| skews.lock().unwrap().push(by_ms)
+        })),
+        ..Default::default()
+    };
+
+    // within tolerance
+    let duration_ms = get_most_recent_duration_ms(&clock, &settings, 100);
+    assert_eq!(duration_ms, Ok(100));
+
+    // beyond tolerance
+    let duration_ms = get_most_recent_duration_ms(&clock, &settings, 101);
+    assert_eq!(duration_ms, Err(Error::ClockMovedBackwards { by_ms: 11 }));
+
+    // past the origin
+    clock.set(origin - Duration::from_millis(2));
+    let duration_ms = get_most_recent_duration_ms(&clock, &settings, 100);
+    assert_eq!(duration_ms, Err(Error::ClockMovedBackwards { by_ms: 102 }));
+
+    // time moved forward, the hook is quiet
+    clock.set(origin + Duration::from_millis(100));
+    let duration_ms = get_most_recent_duration_ms(&clock, &settings, 100);
+    assert_eq!(duration_ms, Ok(100));
+
+    assert_eq!(*skews.lock().unwrap(), vec![10, 11, 102]);
+}
 
-    assert_eq!(greater_duration_ms, duration_ms);
+#[test]
+fn test_snowprint_with_manual_clock() {
+    let origin = SystemTime::now();
+    let clock = ManualClock::new(origin + Duration::from_millis(10));
+    let settings = Settings {
+        origin_system_time: origin,
+        logical_volume_base: 0,
+        logical_volume_length: 8192,
+        ..Default::default()
+    };
+    let mut snowprinter = match Snowprint::with_clock(settings, clock.clone()) {
+        Ok(snow) => snow,
+        Err(err) => panic!("settings should be valid: {:?}", err),
+    };
 
-    let greater_duration_ms = get_most_recent_duration_ms(duration, greater_duration_ms);
-    assert_eq!(greater_duration_ms, duration_ms);
+    assert_eq!(
+        snowprinter.compose(),
+        Ok(SnowprintId::from(compose(10, 0, 1)))
+    );
+    clock.advance(Duration::from_millis(1));
+    assert_eq!(
+        snowprinter.compose(),
+        Ok(SnowprintId::from(compose(11, 1, 0)))
+    );
+    clock.rewind(Duration::from_millis(5));
+    assert_eq!(
+        snowprinter.compose(),
+        Ok(SnowprintId::from(compose(11, 1, 1)))
+    );
 }
 
 #[test]
@@ -119,6 +207,7 @@ fn test_compose_from_settings_and_state() {
         origin_system_time: SystemTime::now(),
         logical_volume_base: 4096,
         logical_volume_length: 4096,
+        ..Default::default()
     };
     let mut state = State {
         prev_duration_ms: 0,
@@ -136,7 +225,7 @@ fn test_compose_from_settings_and_state() {
             assert_eq!(sequence, 256);
         }
         // error by comparing result to incorrect error
-        Err(err) => assert_eq!(Error::ExceededAvailableLogicalVolumes, err),
+        Err(err) => assert_eq!(Error::LogicalVolumeModuloIsZero, err),
     }
 
     // fail out
@@ -167,6 +256,226 @@ fn test_compose_from_settings_and_state() {
             assert_eq!(sequence, 0);
         }
         // error by comparing result to incorrect error
-        Err(err) => assert_eq!(Error::ExceededAvailableLogicalVolumes, err),
+        Err(err) => assert_eq!(Error::LogicalVolumeModuloIsZero, err),
+    }
+}
+
+#[test]
+fn test_compose_from_settings_and_state_exceeds_timestamp() {
+    let settings = Settings {
+        origin_system_time: SystemTime::now(),
+        logical_volume_base: 0,
+        logical_volume_length: 8192,
+        ..Default::default()
+    };
+    let mut state = State {
+        prev_duration_ms: MAX_TIMESTAMP,
+        sequence: 0,
+        logical_volume: 0,
+        prev_logical_volume: 0,
+    };
+
+    let snowprint = compose_from_settings_and_state(&settings, &mut state, MAX_TIMESTAMP);
+    assert_eq!(snowprint, Ok(compose(MAX_TIMESTAMP, 0, 1)));
+
+    let snowprint = compose_from_settings_and_state(&settings, &mut state, MAX_TIMESTAMP + 1);
+    assert_eq!(
+        snowprint,
+        Err(Error::ExceededTimestampBitLength {
+            timestamp_ms: MAX_TIMESTAMP + 1,
+        })
+    );
+}
+
+#[test]
+fn test_get_available_sequences() {
+    let state = State {
+        prev_duration_ms: 0,
+        sequence: 1,
+        logical_volume: 0,
+        prev_logical_volume: 0,
+    };
+    assert_eq!(get_available_sequences(&state, 8192), 1022 + 8191 * 1024);
+    assert_eq!(get_available_sequences(&state, 1), 1022);
+
+    let state = State {
+        prev_duration_ms: 0,
+        sequence: 1023,
+        logical_volume: 3,
+        prev_logical_volume: 2,
+    };
+    assert_eq!(get_available_sequences(&state, 4), 2 * 1024);
+
+    let state = State {
+        prev_duration_ms: 0,
+        sequence: 1023,
+        logical_volume: 1,
+        prev_logical_volume: 2,
+    };
+    assert_eq!(get_available_sequences(&state, 4), 0);
+}
+
+#[test]
+fn test_modify_state_skip_sequences() {
+    let mut state = State {
+        prev_duration_ms: 0,
+        sequence: 1000,
+        logical_volume: 3,
+        prev_logical_volume: 0,
+    };
+    let expected_state = State {
+        prev_duration_ms: 0,
+        sequence: 952,
+        logical_volume: 1,
+        prev_logical_volume: 0,
+    };
+    modify_state_skip_sequences(&mut state, 4, 2000);
+    assert_eq!(expected_state, state);
+}
+
+#[test]
+fn test_reserve_from_settings_and_state_matches_compose() {
+    let settings = Settings {
+        origin_system_time: SystemTime::now(),
+        logical_volume_base: 1024,
+        logical_volume_length: 3,
+        ..Default::default()
+    };
+    let mut state = State {
+        prev_duration_ms: 0,
+        sequence: 0,
+        logical_volume: 0,
+        prev_logical_volume: 0,
+    };
+    let mut expected_state = state.clone();
+
+    for (duration_ms, length) in [(0, 1500), (0, 0), (0, 1), (1, 2047), (2, 2048)] {
+        let range = reserve_from_settings_and_state(
+            &settings,
+            &mut state,
+            &VolumeSequences::default(),
+            duration_ms,
+            length,
+            false,
+        );
+        let range = match range {
+            Ok(range) => range,
+            Err(err) => panic!("range should be reserved: {:?}", err),
+        };
+        assert_eq!(range.len(), length);
+        for snowprint in range {
+            let expected =
+                compose_from_settings_and_state(&settings, &mut expected_state, duration_ms);
+            assert_eq!(expected.map(SnowprintId::from), Ok(snowprint));
+        }
+        assert_eq!(expected_state, state);
     }
+
+    // ms 2 has no room left, nothing is reserved
+    let range = reserve_from_settings_and_state(
+        &settings,
+        &mut state,
+        &VolumeSequences::default(),
+        2,
+        1,
+        false,
+    );
+    assert_eq!(range, Err(Error::ExceededAvailableSequences));
+    assert_eq!(expected_state, state);
+}
+
+#[test]
+fn test_reserve_from_settings_and_state_allow_fewer() {
+    let settings = Settings {
+        origin_system_time: SystemTime::now(),
+        logical_volume_base: 0,
+        logical_volume_length: 1,
+        ..Default::default()
+    };
+    let mut state = State {
+        prev_duration_ms: 0,
+        sequence: 1000,
+        logical_volume: 0,
+        prev_logical_volume: 0,
+    };
+
+    let range = reserve_from_settings_and_state(
+        &settings,
+        &mut state,
+        &VolumeSequences::default(),
+        0,
+        100,
+        false,
+    );
+    assert_eq!(range, Err(Error::ExceededAvailableSequences));
+
+    let range = reserve_from_settings_and_state(
+        &settings,
+        &mut state,
+        &VolumeSequences::default(),
+        0,
+        100,
+        true,
+    );
+    let snowprints: Vec<u64> = match range {
+        Ok(range) => range.map(u64::from).collect(),
+        Err(err) => panic!("range should be reserved: {:?}", err),
+    };
+    assert_eq!(snowprints.len(), 23);
+    assert_eq!(snowprints[0], compose(0, 0, 1001));
+    assert_eq!(snowprints[22], compose(0, 0, 1023));
+}
+
+#[test]
+fn test_compose_from_settings_and_state_strictly_increasing() {
+    let settings = Settings {
+        origin_system_time: SystemTime::now(),
+        logical_volume_base: 0,
+        logical_volume_length: 4,
+        strictly_increasing: true,
+        ..Default::default()
+    };
+    let mut state = State {
+        prev_duration_ms: 0,
+        sequence: 5,
+        logical_volume: 1,
+        prev_logical_volume: 0,
+    };
+
+    // the ms starts on the next logical volume but stops before index 0
+    let snowprint = compose_from_settings_and_state(&settings, &mut state, 1);
+    assert_eq!(snowprint, Ok(compose(1, 2, 0)));
+    assert_eq!(state.prev_logical_volume, 0);
+    assert_eq!(get_available_sequences(&state, 4), 1023 + 1024);
+
+    state.sequence = 1023;
+    let snowprint = compose_from_settings_and_state(&settings, &mut state, 1);
+    assert_eq!(snowprint, Ok(compose(1, 3, 0)));
+
+    state.sequence = 1023;
+    let snowprint = compose_from_settings_and_state(&settings, &mut state, 1);
+    assert_eq!(snowprint, Err(Error::ExceededAvailableSequences));
+
+    // a ms that starts on index 0 can use every logical volume
+    let snowprint = compose_from_settings_and_state(&settings, &mut state, 2);
+    assert_eq!(snowprint, Ok(compose(2, 0, 0)));
+    assert_eq!(get_available_sequences(&state, 4), 1023 + 3 * 1024);
+}
+
+#[test]
+fn test_check_settings_strictly_increasing_set() {
+    let settings = Settings {
+        logical_volume_set: Some(LogicalVolumeSet::new([3, 70, 5])),
+        ..Default::default()
+    };
+    assert_eq!(check_settings(&settings), Ok(()));
+
+    let settings = Settings {
+        strictly_increasing: true,
+        ..settings
+    };
+    assert_eq!(
+        check_settings(&settings),
+        Err(Error::UnorderedLogicalVolumeSet { logical_volume: 5 })
+    );
 }
@@ -0,0 +1,157 @@
+// Arbitrary sets of logical volumes, used instead of a contiguous range
+//     - within a ms, exhaustion rotates through the set in the order given
+//     - without weights each ms starts on the next logical volume, like a range
+//     - with weights each ms starts on a logical volume picked by weight
+
+// Weighted picks hash the ms instead of keeping a cursor, so Snowprint and
+// SyncSnowprint pick the same logical volume without any extra state.
+
+use crate::hash::mix;
+use crate::{Error, MAX_LOGICAL_VOLUMES};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WeightedLogicalVolume {
+    pub logical_volume: u64,
+    #[cfg_attr(feature = "serde", serde(default = "default_weight"))]
+    pub weight: u64,
+}
+
+// clones are cheap, settings are cloned into every SnowprintRange
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(
+        from = "Vec<WeightedLogicalVolume>",
+        into = "Vec<WeightedLogicalVolume>"
+    )
+)]
+pub struct LogicalVolumeSet {
+    logical_volumes: Arc<[u64]>,
+    weights: Option<Arc<[u64]>>,
+}
+
+impl LogicalVolumeSet {
+    pub fn new(logical_volumes: impl IntoIterator<Item = u64>) -> LogicalVolumeSet {
+        LogicalVolumeSet {
+            logical_volumes: logical_volumes.into_iter().collect(),
+            weights: None,
+        }
+    }
+
+    // a logical volume with twice the weight starts twice as many ms
+    pub fn weighted(
+        weighted_logical_volumes: impl IntoIterator<Item = WeightedLogicalVolume>,
+    ) -> LogicalVolumeSet {
+        let (logical_volumes, weights): (Vec<u64>, Vec<u64>) = weighted_logical_volumes
+            .into_iter()
+            .map(|weighted| (weighted.logical_volume, weighted.weight))
+            .unzip();
+
+        // equal weights rotate evenly without hashing
+        let is_uniform = weights.windows(2).all(|pair| pair[0] == pair[1]);
+        LogicalVolumeSet {
+            logical_volumes: logical_volumes.into(),
+            weights: match is_uniform && weights.first() != Some(&0) {
+                true => None,
+                _ => Some(weights.into()),
+            },
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.logical_volumes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.logical_volumes.is_empty()
+    }
+
+    pub fn logical_volumes(&self) -> &[u64] {
+        &self.logical_volumes
+    }
+
+    pub fn weight(&self, index: usize) -> u64 {
+        match &self.weights {
+            Some(weights) => weights[index],
+            _ => 1,
+        }
+    }
+
+    pub(crate) fn get(&self, index: u64) -> u64 {
+        self.logical_volumes[index as usize]
+    }
+
+    // the index a ms should start on, None when rotating evenly
+    pub(crate) fn pick(&self, duration_ms: u64) -> Option<u64> {
+        let weights = self.weights.as_ref()?;
+        let total_weight: u64 = weights.iter().sum();
+
+        let mut remainder = mix(duration_ms) % total_weight;
+        for (index, &weight) in weights.iter().enumerate() {
+            if remainder < weight {
+                return Some(index as u64);
+            }
+            remainder -= weight;
+        }
+
+        None
+    }
+}
+
+impl From<Vec<WeightedLogicalVolume>> for LogicalVolumeSet {
+    fn from(weighted_logical_volumes: Vec<WeightedLogicalVolume>) -> LogicalVolumeSet {
+        LogicalVolumeSet::weighted(weighted_logical_volumes)
+    }
+}
+
+impl From<LogicalVolumeSet> for Vec<WeightedLogicalVolume> {
+    fn from(logical_volume_set: LogicalVolumeSet) -> Vec<WeightedLogicalVolume> {
+        (0..logical_volume_set.len())
+            .map(|index| WeightedLogicalVolume {
+                logical_volume: logical_volume_set.logical_volumes[index],
+                weight: logical_volume_set.weight(index),
+            })
+            .collect()
+    }
+}
+
+pub(crate) fn check_logical_volume_set(logical_volume_set: &LogicalVolumeSet) -> Result<(), Error> {
+    if logical_volume_set.is_empty() {
+        return Err(Error::EmptyLogicalVolumeSet);
+    }
+
+    let mut seen = HashSet::with_capacity(logical_volume_set.len());
+    for &logical_volume in logical_volume_set.logical_volumes.iter() {
+        if MAX_LOGICAL_VOLUMES <= logical_volume {
+            return Err(Error::ExceededLogicalVolumeBitLength { logical_volume });
+        }
+        if !seen.insert(logical_volume) {
+            return Err(Error::DuplicateLogicalVolume { logical_volume });
+        }
+    }
+
+    if let Some(weights) = &logical_volume_set.weights {
+        let mut total_weight: u64 = 0;
+        for (index, &weight) in weights.iter().enumerate() {
+            let logical_volume = logical_volume_set.logical_volumes[index];
+            if weight == 0 {
+                return Err(Error::LogicalVolumeWeightIsZero { logical_volume });
+            }
+            total_weight = match total_weight.checked_add(weight) {
+                Some(total_weight) => total_weight,
+                _ => return Err(Error::ExceededTotalLogicalVolumeWeight),
+            };
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "serde")]
+fn default_weight() -> u64 {
+    1
+}
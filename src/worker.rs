@@ -0,0 +1,67 @@
+// Settings derived from a stable worker number, for deployments without a coordinator
+//     - worker n rotates logical volumes n * length up to (n + 1) * length
+//     - workers are disjoint as long as no two share a number
+//     - numbers come from an env var, a StatefulSet hostname ordinal, or ip address low bits
+
+use crate::{check_settings, Error, Settings, MAX_LOGICAL_VOLUMES};
+use std::env;
+use std::net::IpAddr;
+
+impl Settings {
+    pub fn for_worker(worker_id: u64, logical_volume_length: u64) -> Result<Settings, Error> {
+        let settings = Settings {
+            logical_volume_base: worker_id.saturating_mul(logical_volume_length),
+            logical_volume_length,
+            ..Settings::default()
+        };
+        check_settings(&settings)?;
+
+        Ok(settings)
+    }
+
+    pub fn for_worker_from_env(name: &str, logical_volume_length: u64) -> Result<Settings, Error> {
+        let worker_id = match env::var(name) {
+            Ok(worker_id) => worker_id,
+            Err(env::VarError::NotPresent) => return Err(Error::MissingWorkerId),
+            _ => return Err(Error::FailedToParseWorkerId),
+        };
+        match worker_id.trim().parse::<u64>() {
+            Ok(worker_id) => Settings::for_worker(worker_id, logical_volume_length),
+            _ => Err(Error::FailedToParseWorkerId),
+        }
+    }
+
+    // StatefulSet pods are named <statefulset>-<ordinal>, a domain after the name is ignored
+    pub fn for_worker_from_hostname(
+        hostname: &str,
+        logical_volume_length: u64,
+    ) -> Result<Settings, Error> {
+        let name = hostname.split('.').next().unwrap_or_default();
+        let ordinal = match name.rsplit_once('-') {
+            Some((_, ordinal)) if ordinal.bytes().all(|byte| byte.is_ascii_digit()) => ordinal,
+            _ => return Err(Error::FailedToParseWorkerId),
+        };
+        match ordinal.parse::<u64>() {
+            Ok(worker_id) => Settings::for_worker(worker_id, logical_volume_length),
+            _ => Err(Error::FailedToParseWorkerId),
+        }
+    }
+
+    // uses as many low bits of the address as there are whole workers' worth of volumes,
+    // hosts must differ in those bits, e.g. the last octet for a length of 32
+    pub fn for_worker_from_ip(ip: IpAddr, logical_volume_length: u64) -> Result<Settings, Error> {
+        let address = match ip {
+            IpAddr::V4(ip) => u32::from(ip) as u64,
+            IpAddr::V6(ip) => u128::from(ip) as u64,
+        };
+        let worker_bit_len = match logical_volume_length {
+            0 => 0,
+            _ => (MAX_LOGICAL_VOLUMES / logical_volume_length)
+                .checked_ilog2()
+                .unwrap_or(0),
+        };
+        let worker_id = address & ((1 << worker_bit_len) - 1);
+
+        Settings::for_worker(worker_id, logical_volume_length)
+    }
+}
@@ -0,0 +1,364 @@
+use snowprints::{
+    ClockSkewHook, Error, ExhaustionPolicy, LogicalVolumeSet, ManualClock, Settings, Snowprint,
+    SnowprintId,
+};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+
+const JANUARY_1ST_2024_AS_MS: u64 = 1704096000000;
+const JANUARY_1ST_2024_AS_DURATION: Duration = Duration::from_millis(JANUARY_1ST_2024_AS_MS);
+
+fn settings(logical_volume_base: u64, logical_volume_length: u64) -> Settings {
+    Settings {
+        origin_system_time: UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION,
+        logical_volume_base,
+        logical_volume_length,
+        ..Default::default()
+    }
+}
+
+fn snowprinter(settings: Settings) -> (Snowprint<ManualClock>, ManualClock) {
+    let clock = ManualClock::new(UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION);
+    let snowprinter = Snowprint::with_clock(settings, clock.clone()).unwrap();
+    (snowprinter, clock)
+}
+
+#[test]
+fn same_key_composes_on_the_same_logical_volume() {
+    let settings = settings(1024, 1024);
+    let (mut snowprinter, clock) = snowprinter(settings.clone());
+    let logical_volume = settings.logical_volume_for_key("tenant-42").unwrap();
+    assert!((1024..2048).contains(&logical_volume));
+
+    for _ in 0..100 {
+        clock.advance(Duration::from_millis(1));
+        let snowprint = snowprinter.compose_for_key("tenant-42").unwrap();
+        assert_eq!(snowprint.logical_volume(), logical_volume);
+        snowprinter.compose().unwrap();
+    }
+}
+
+#[test]
+fn keys_are_stable_and_spread_across_logical_volumes() {
+    let settings = settings(0, 8192);
+    assert_eq!(settings.logical_volume_for_key("tenant-42"), Ok(2690));
+    assert_eq!(
+        settings.logical_volume_for_key(42u64.to_be_bytes()),
+        Ok(7820)
+    );
+
+    let logical_volumes: HashSet<u64> = (0..1000)
+        .map(|key: u64| settings.logical_volume_for_key(key.to_be_bytes()).unwrap())
+        .collect();
+    assert!(900 < logical_volumes.len());
+
+    let settings = Settings {
+        logical_volume_set: Some(LogicalVolumeSet::new([3, 70, 5])),
+        ..settings
+    };
+    for key in 0..100u64 {
+        let logical_volume = settings.logical_volume_for_key(key.to_be_bytes()).unwrap();
+        assert!([3, 70, 5].contains(&logical_volume));
+    }
+
+    let settings = Settings {
+        logical_volume_length: 0,
+        ..Default::default()
+    };
+    assert_eq!(
+        settings.logical_volume_for_key("tenant-42"),
+        Err(Error::LogicalVolumeModuloIsZero)
+    );
+}
+
+#[test]
+fn keyed_snowprints_exhaust_their_logical_volume() {
+    let (mut snowprinter, clock) = snowprinter(settings(0, 8192));
+    clock.advance(Duration::from_millis(1));
+
+    for sequence in 0..1024 {
+        let snowprint = snowprinter.compose_for_key("tenant-42").unwrap();
+        assert_eq!(snowprint.sequence(), sequence);
+    }
+    assert_eq!(
+        snowprinter.compose_for_key("tenant-42"),
+        Err(Error::ExceededAvailableSequences)
+    );
+
+    // other keys and the rotation are unaffected
+    assert!(snowprinter.compose_for_key("tenant-7").is_ok());
+    assert!(snowprinter.compose().is_ok());
+
+    clock.advance(Duration::from_millis(1));
+    assert!(snowprinter.compose_for_key("tenant-42").is_ok());
+}
+
+#[test]
+fn keyed_snowprints_wait_with_exhaustion_policy() {
+    let settings = Settings {
+        exhaustion_policy: ExhaustionPolicy::Spin,
+        ..settings(0, 8192)
+    };
+    let clock = ManualClock::new(UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION);
+    let mut snowprinter = Snowprint::with_clock(settings, clock.clone()).unwrap();
+    clock.advance(Duration::from_millis(1));
+
+    for _ in 0..1024 {
+        snowprinter.compose_for_key("tenant-42").unwrap();
+    }
+
+    let handle = {
+        let clock = clock.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            clock.advance(Duration::from_millis(1));
+        })
+    };
+    let snowprint = snowprinter.compose_for_key("tenant-42").unwrap();
+    handle.join().unwrap();
+    assert_eq!(snowprint.timestamp_ms(), 2);
+    assert_eq!(snowprint.sequence(), 0);
+}
+
+#[test]
+fn keyed_snowprints_do_not_collide_with_the_rotation() {
+    for logical_volume_length in [1, 2, 3, 8] {
+        let (mut snowprinter, clock) = snowprinter(settings(100, logical_volume_length));
+        let keys: Vec<String> = (0..16).map(|key| format!("tenant-{}", key)).collect();
+
+        // steps share a ms in pairs, so keep one set per ms
+        let mut unique: HashSet<SnowprintId> = HashSet::new();
+        for step in 0..6 {
+            if step % 2 == 0 {
+                clock.advance(Duration::from_millis(1));
+                unique.clear();
+            }
+
+            let mut exhausted = (false, false, false);
+            let mut turn = 0;
+            while !(exhausted.0 && exhausted.1 && exhausted.2) {
+                turn += 1;
+                match turn % 3 {
+                    0 => match snowprinter.compose_for_key(&keys[turn % keys.len()]) {
+                        Ok(snowprint) => assert!(unique.insert(snowprint)),
+                        Err(_) => exhausted.0 = true,
+                    },
+                    1 => match snowprinter.compose() {
+                        Ok(snowprint) => assert!(unique.insert(snowprint)),
+                        Err(_) => exhausted.1 = true,
+                    },
+                    _ => match snowprinter.reserve(300) {
+                        Ok(range) => {
+                            for snowprint in range {
+                                assert!(unique.insert(snowprint));
+                            }
+                        }
+                        Err(_) => exhausted.2 = exhausted.1,
+                    },
+                }
+            }
+
+            // every logical volume can hold 1024 sequences each ms
+            assert!(unique.len() as u64 <= logical_volume_length * 1024);
+        }
+    }
+}
+
+#[test]
+fn keyed_snowprints_do_not_collide_in_the_first_ms() {
+    for logical_volume_length in [2, 3, 8] {
+        let (mut snowprinter, _clock) = snowprinter(settings(100, logical_volume_length));
+        let keys: Vec<String> = (0..16).map(|key| format!("tenant-{}", key)).collect();
+
+        // the rotation starts the first ms on the first logical volume
+        let mut unique: HashSet<SnowprintId> = HashSet::new();
+        let mut exhausted = (false, false);
+        let mut turn = 0;
+        while !(exhausted.0 && exhausted.1) {
+            turn += 1;
+            let snowprint = match turn % 2 {
+                0 => snowprinter.compose_for_key(&keys[turn % keys.len()]),
+                _ => snowprinter.compose(),
+            };
+            match snowprint {
+                Ok(snowprint) => assert!(unique.insert(snowprint)),
+                Err(_) if turn % 2 == 0 => exhausted.0 = true,
+                Err(_) => exhausted.1 = true,
+            }
+        }
+        assert!(unique.len() as u64 <= logical_volume_length * 1024);
+    }
+}
+
+#[test]
+fn keyed_snowprints_survive_the_clock_moving_back() {
+    for logical_volume_length in [1, 8] {
+        let (mut snowprinter, clock) = snowprinter(settings(0, logical_volume_length));
+        clock.advance(Duration::from_millis(5));
+        let first = snowprinter.compose_for_key("tenant").unwrap();
+        clock.advance(Duration::from_millis(1));
+        let second = snowprinter.compose_for_key("tenant").unwrap();
+
+        // keyed snowprints stay on the most recent ms, and so does the rotation
+        clock.rewind(Duration::from_millis(1));
+        let third = snowprinter.compose_for_key("tenant").unwrap();
+        let rotated = snowprinter.compose().unwrap();
+        assert_eq!(third.timestamp_ms(), 6);
+        assert_eq!(rotated.timestamp_ms(), 6);
+
+        let unique: HashSet<SnowprintId> = [first, second, third, rotated].into();
+        assert_eq!(unique.len(), 4);
+    }
+}
+
+#[test]
+fn keyed_snowprints_report_the_clock_moving_back() {
+    let alerts = Arc::new(AtomicU64::new(0));
+    let settings = Settings {
+        max_backward_skew_ms: Some(2),
+        clock_skew_hook: Some(ClockSkewHook::new({
+            let alerts = alerts.clone();
+            move |_by_ms| {
+                alerts.fetch_add(1, Ordering::Relaxed);
+            }
+        })),
+        ..settings(0, 8)
+    };
+    let (mut snowprinter, clock) = snowprinter(settings);
+    clock.advance(Duration::from_millis(10));
+    snowprinter.compose_for_key("tenant").unwrap();
+
+    clock.rewind(Duration::from_millis(1));
+    assert!(snowprinter.compose_for_key("tenant").is_ok());
+    assert_eq!(alerts.load(Ordering::Relaxed), 1);
+
+    clock.rewind(Duration::from_millis(2));
+    assert_eq!(
+        snowprinter.compose_for_key("tenant"),
+        Err(Error::ClockMovedBackwards { by_ms: 3 })
+    );
+    assert_eq!(alerts.load(Ordering::Relaxed), 2);
+}
+
+#[test]
+fn children_compose_on_their_parents_logical_volume() {
+    let (mut snowprinter, clock) = snowprinter(settings(1024, 1024));
+    clock.advance(Duration::from_millis(1));
+    let parent = snowprinter.compose().unwrap();
+
+    clock.advance(Duration::from_millis(5));
+    snowprinter.compose().unwrap();
+    let child = snowprinter.compose_child_of(parent).unwrap();
+
+    assert_eq!(child.logical_volume(), parent.logical_volume());
+    assert_eq!(child.timestamp_ms(), 6);
+    assert!(parent < child);
+
+    let grandchild = snowprinter.compose_child_of(child).unwrap();
+    assert_eq!(grandchild.logical_volume(), parent.logical_volume());
+    assert!(child < grandchild);
+}
+
+#[test]
+fn children_follow_logical_volume_sets() {
+    let settings = Settings {
+        logical_volume_set: Some(LogicalVolumeSet::new([3, 70, 5])),
+        ..settings(0, 8192)
+    };
+    let (mut snowprinter, clock) = snowprinter(settings);
+    clock.advance(Duration::from_millis(1));
+
+    let parent = snowprinter.compose_for_key("post-1").unwrap();
+    let child = snowprinter.compose_child_of(parent).unwrap();
+    assert_eq!(child.logical_volume(), parent.logical_volume());
+    assert_ne!(child, parent);
+
+    let stranger = SnowprintId::from(snowprints::compose(1, 4, 0));
+    assert_eq!(
+        snowprinter.compose_child_of(stranger),
+        Err(Error::LogicalVolumeNotInSettings { logical_volume: 4 })
+    );
+}
+
+#[test]
+fn children_reject_logical_volumes_outside_settings() {
+    let (mut snowprinter, _clock) = snowprinter(settings(1024, 1024));
+    for logical_volume in [0, 1023, 2048, 8191] {
+        let parent = SnowprintId::from(snowprints::compose(1, logical_volume, 0));
+        assert_eq!(
+            snowprinter.compose_child_of(parent),
+            Err(Error::LogicalVolumeNotInSettings { logical_volume })
+        );
+    }
+}
+
+#[test]
+fn children_do_not_collide_with_the_rotation() {
+    let (mut snowprinter, clock) = snowprinter(settings(0, 2));
+    clock.advance(Duration::from_millis(1));
+
+    let parents = [
+        SnowprintId::from(snowprints::compose(0, 0, 0)),
+        SnowprintId::from(snowprints::compose(0, 1, 0)),
+    ];
+    let mut unique = HashSet::new();
+    let mut turn = 0;
+    let mut failures = 0;
+    while failures < 6 {
+        turn += 1;
+        let snowprint = match turn % 3 {
+            0 => snowprinter.compose(),
+            _ => snowprinter.compose_child_of(parents[turn % 2]),
+        };
+        match snowprint {
+            Ok(snowprint) => {
+                assert!(unique.insert(snowprint));
+                failures = 0;
+            }
+            Err(err) => {
+                assert_eq!(err, Error::ExceededAvailableSequences);
+                failures += 1;
+            }
+        }
+    }
+    assert_eq!(unique.len(), 2 * 1024);
+}
+
+#[test]
+fn children_do_not_collide_in_the_first_ms() {
+    let (mut snowprinter, _clock) = snowprinter(settings(100, 2));
+
+    // the rotation starts the generator's first ms on the parent's logical volume
+    let first = snowprinter.compose().unwrap();
+    let mut unique: HashSet<SnowprintId> = [first].into();
+    while let Ok(snowprint) = snowprinter.compose() {
+        assert!(unique.insert(snowprint));
+    }
+    for _ in 0..2 {
+        match snowprinter.compose_child_of(first) {
+            Ok(child) => assert!(unique.insert(child)),
+            Err(err) => assert_eq!(err, Error::ExceededAvailableSequences),
+        }
+    }
+}
+
+#[test]
+fn children_do_not_collide_when_the_clock_moves_back() {
+    let (mut snowprinter, clock) = snowprinter(settings(0, 2));
+    let parent = SnowprintId::from(snowprints::compose(0, 1, 0));
+
+    clock.advance(Duration::from_millis(10));
+    let first = snowprinter.compose_child_of(parent).unwrap();
+    clock.advance(Duration::from_millis(1));
+    let second = snowprinter.compose_child_of(parent).unwrap();
+
+    clock.rewind(Duration::from_millis(1));
+    let third = snowprinter.compose_child_of(parent).unwrap();
+    let rotated = snowprinter.compose().unwrap();
+    assert_eq!(third.timestamp_ms(), 11);
+
+    let unique: HashSet<SnowprintId> = [first, second, third, rotated].into();
+    assert_eq!(unique.len(), 4);
+}
@@ -0,0 +1,62 @@
+#![cfg(feature = "async")]
+
+use snowprints::{decompose, AsyncSnowprint, ManualClock, Settings};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+
+const JANUARY_1ST_2024_AS_MS: u64 = 1704096000000;
+const JANUARY_1ST_2024_AS_DURATION: Duration = Duration::from_millis(JANUARY_1ST_2024_AS_MS);
+
+#[tokio::test]
+async fn async_snowprint_builds_and_returns_snowprint() {
+    let settings = Settings {
+        origin_system_time: UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION,
+        logical_volume_base: 0,
+        logical_volume_length: 8192,
+        ..Default::default()
+    };
+
+    let snowprinter = AsyncSnowprint::new(settings).expect("settings should be valid");
+    let snowprint = snowprinter
+        .compose()
+        .await
+        .expect("snowprint should compose");
+
+    assert!(snowprint.logical_volume() < 2);
+    assert!(snowprint.sequence() < 2);
+}
+
+#[tokio::test]
+async fn async_snowprint_waits_for_next_ms_when_exhausted() {
+    let origin = UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION;
+    let clock = ManualClock::new(origin);
+    let settings = Settings {
+        origin_system_time: origin,
+        logical_volume_base: 0,
+        logical_volume_length: 1,
+        ..Default::default()
+    };
+    let snowprinter = Arc::new(AsyncSnowprint::with_clock(settings, clock.clone()).unwrap());
+
+    let mut unique = HashSet::new();
+    for _ in 1..1024 {
+        let snowprint = snowprinter.compose().await.expect("sequences available");
+        assert!(unique.insert(snowprint));
+    }
+
+    // the runtime keeps running other tasks while compose waits
+    let waiting = tokio::spawn({
+        let snowprinter = snowprinter.clone();
+        async move { snowprinter.compose().await }
+    });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert!(!waiting.is_finished());
+
+    clock.advance(Duration::from_millis(1));
+    let snowprint = waiting
+        .await
+        .expect("task should not panic")
+        .expect("next ms is available");
+    assert_eq!(decompose(snowprint.into()), (1, 0, 0));
+}
@@ -0,0 +1,261 @@
+use snowprints::{
+    compose, AuditReport, AuditSettings, Auditor, Finding, ManualClock, Settings, Snowprint,
+    SnowprintId,
+};
+use std::time::{Duration, UNIX_EPOCH};
+
+const JANUARY_1ST_2024_AS_MS: u64 = 1704096000000;
+const JANUARY_1ST_2024_AS_DURATION: Duration = Duration::from_millis(JANUARY_1ST_2024_AS_MS);
+
+fn audit_settings(window_ms: Option<u64>) -> AuditSettings {
+    AuditSettings {
+        origin_system_time: UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION,
+        window_ms,
+        max_future_ms: 10,
+    }
+}
+
+// the clock sits 1000ms past the origin
+fn auditor(window_ms: Option<u64>) -> Auditor<ManualClock> {
+    let clock =
+        ManualClock::new(UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION + Duration::from_secs(1));
+    Auditor::with_clock(audit_settings(window_ms), clock)
+}
+
+fn id(timestamp_ms: u64, logical_volume: u64, sequence: u64) -> SnowprintId {
+    SnowprintId::from(compose(timestamp_ms, logical_volume, sequence))
+}
+
+#[test]
+fn auditor_passes_disjoint_generators() {
+    let clock =
+        ManualClock::new(UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION + Duration::from_secs(1));
+    let mut auditor = Auditor::with_clock(audit_settings(Some(4)), clock.clone());
+
+    let mut snowprinters = Vec::new();
+    for (name, logical_volume_base) in [("first", 0), ("second", 16), ("third", 32)] {
+        let settings = Settings {
+            origin_system_time: UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION,
+            logical_volume_base,
+            logical_volume_length: 16,
+            ..Default::default()
+        };
+        auditor.register(name, &settings);
+        snowprinters.push((
+            name,
+            Snowprint::with_clock(settings, clock.clone()).unwrap(),
+        ));
+    }
+
+    for _ in 0..50 {
+        for (name, snowprinter) in &mut snowprinters {
+            for _ in 0..100 {
+                let snowprint = snowprinter.compose().unwrap();
+                assert_eq!(auditor.observe(name, snowprint), Vec::new());
+            }
+        }
+        clock.advance(Duration::from_millis(1));
+    }
+
+    let report = auditor.report();
+    assert!(report.is_clean());
+    assert_eq!(report.observed, 15_000);
+    assert_eq!(report.unchecked, 0);
+}
+
+#[test]
+fn auditor_reports_duplicates_across_generators() {
+    let mut auditor = auditor(None);
+
+    assert!(auditor.observe("first", id(500, 1, 0)).is_empty());
+    assert_eq!(
+        auditor.observe("second", id(500, 1, 0)),
+        vec![Finding::Duplicate {
+            generator: "second".to_string(),
+            first_generator: "first".to_string(),
+            snowprint: id(500, 1, 0),
+        }]
+    );
+    assert_eq!(auditor.report().duplicates, 1);
+    assert!(!auditor.report().is_clean());
+}
+
+#[test]
+fn auditor_reports_non_monotonic_ids_per_generator() {
+    let mut auditor = auditor(None);
+
+    assert!(auditor.observe("first", id(500, 4095, 0)).is_empty());
+    assert!(auditor.observe("second", id(500, 0, 0)).is_empty());
+    assert_eq!(
+        auditor.observe("first", id(500, 0, 1)),
+        vec![Finding::NonMonotonic {
+            generator: "first".to_string(),
+            snowprint: id(500, 0, 1),
+            previous: id(500, 4095, 0),
+        }]
+    );
+
+    // repeating an id is both a duplicate and not an increase
+    let findings = auditor.observe("first", id(500, 0, 1));
+    assert_eq!(findings.len(), 2);
+    assert_eq!(
+        auditor.report(),
+        AuditReport {
+            observed: 4,
+            duplicates: 1,
+            non_monotonic: 2,
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn auditor_reports_logical_volumes_outside_settings() {
+    let mut auditor = auditor(None);
+    auditor.register(
+        "first",
+        &Settings {
+            logical_volume_base: 1024,
+            logical_volume_length: 16,
+            ..Default::default()
+        },
+    );
+
+    assert!(auditor.observe("first", id(500, 1024, 0)).is_empty());
+    assert!(auditor.observe("first", id(500, 1039, 0)).is_empty());
+    assert_eq!(
+        auditor.observe("first", id(501, 1040, 0)),
+        vec![Finding::LogicalVolumeOutOfRange {
+            generator: "first".to_string(),
+            snowprint: id(501, 1040, 0),
+        }]
+    );
+
+    // unregistered generators are not range checked
+    assert!(auditor.observe("second", id(502, 4000, 0)).is_empty());
+    assert_eq!(auditor.report().out_of_range, 1);
+}
+
+#[test]
+fn auditor_reports_timestamps_from_the_future() {
+    let mut auditor = auditor(None);
+
+    assert!(auditor.observe("first", id(1010, 0, 0)).is_empty());
+    assert_eq!(
+        auditor.observe("first", id(1500, 0, 0)),
+        vec![Finding::FromFuture {
+            generator: "first".to_string(),
+            snowprint: id(1500, 0, 0),
+            ahead_by_ms: 490,
+        }]
+    );
+    assert_eq!(auditor.report().from_future, 1);
+    assert_eq!(auditor.tracked(), 2);
+
+    // two generators running ahead can still collide
+    let findings = auditor.observe("second", id(1500, 0, 0));
+    assert!(findings.contains(&Finding::Duplicate {
+        generator: "second".to_string(),
+        first_generator: "first".to_string(),
+        snowprint: id(1500, 0, 0),
+    }));
+    assert_eq!(auditor.report().duplicates, 1);
+}
+
+#[test]
+fn auditor_window_ignores_timestamps_from_the_future() {
+    let mut auditor = auditor(Some(3));
+
+    assert!(auditor.observe("first", id(1000, 0, 0)).is_empty());
+    assert_eq!(auditor.observe("second", id(1012, 0, 0)).len(), 1);
+
+    // the window still ends at 1000, so older ids are checked
+    assert!(auditor.observe("third", id(998, 0, 0)).is_empty());
+    assert_eq!(auditor.observe("fourth", id(998, 0, 0)).len(), 1);
+    assert_eq!(auditor.report().unchecked, 0);
+}
+
+#[test]
+fn auditor_settings_at_the_limits_do_not_overflow() {
+    let clock =
+        ManualClock::new(UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION + Duration::from_secs(1));
+    let mut auditor = Auditor::with_clock(
+        AuditSettings {
+            origin_system_time: UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION,
+            window_ms: Some(u64::MAX),
+            max_future_ms: u64::MAX,
+        },
+        clock,
+    );
+
+    assert!(auditor.observe("first", id(5000, 0, 0)).is_empty());
+    assert!(auditor.observe("first", id(6000, 0, 0)).is_empty());
+    assert_eq!(auditor.observe("second", id(5000, 0, 0)).len(), 1);
+    assert_eq!(auditor.report().from_future, 0);
+    assert_eq!(auditor.tracked(), 2);
+}
+
+#[test]
+fn auditor_window_bounds_memory() {
+    let mut auditor = auditor(Some(3));
+
+    for timestamp_ms in 0..100 {
+        for sequence in 0..10 {
+            assert!(auditor
+                .observe("first", id(timestamp_ms, 0, sequence))
+                .is_empty());
+        }
+        assert!(auditor.tracked() <= 30);
+    }
+    assert_eq!(auditor.tracked(), 30);
+
+    // duplicates within the window are still caught
+    assert_eq!(auditor.observe("second", id(97, 0, 0)).len(), 1);
+
+    // older ids can't be checked and are counted instead
+    assert!(auditor.observe("third", id(96, 0, 0)).is_empty());
+    assert_eq!(auditor.report().unchecked, 1);
+    assert_eq!(auditor.report().duplicates, 1);
+}
+
+#[test]
+fn auditor_window_bounds_memory_for_timestamps_from_the_future() {
+    let clock =
+        ManualClock::new(UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION + Duration::from_secs(1));
+    let mut auditor = Auditor::with_clock(audit_settings(Some(3)), clock.clone());
+
+    // the horizon is 1010, ids more than 3ms past it are counted instead of kept
+    assert_eq!(auditor.observe("first", id(1013, 0, 0)).len(), 1);
+    assert_eq!(auditor.observe("first", id(1014, 0, 0)).len(), 1);
+    assert_eq!(auditor.report().unchecked, 1);
+    assert_eq!(auditor.tracked(), 1);
+
+    // a generator running ahead as the clock moves is evicted behind the horizon
+    for timestamp_ms in 1013..2000 {
+        for sequence in 0..10 {
+            auditor.observe("second", id(timestamp_ms, 1, sequence));
+        }
+        clock.advance(Duration::from_millis(1));
+        assert!(auditor.tracked() <= 70);
+    }
+
+    // duplicates ahead of the clock are still caught
+    assert!(auditor
+        .observe("third", id(1999, 1, 0))
+        .contains(&Finding::Duplicate {
+            generator: "third".to_string(),
+            first_generator: "second".to_string(),
+            snowprint: id(1999, 1, 0),
+        }));
+}
+
+#[test]
+fn auditor_without_a_window_keeps_every_id() {
+    let mut auditor = auditor(None);
+
+    for timestamp_ms in 0..100 {
+        auditor.observe("first", id(timestamp_ms, 0, 0));
+    }
+    assert_eq!(auditor.tracked(), 100);
+    assert_eq!(auditor.observe("second", id(0, 0, 0)).len(), 1);
+}
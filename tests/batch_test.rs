@@ -0,0 +1,96 @@
+use snowprints::{Error, ExhaustionPolicy, ManualClock, Settings, Snowprint, SnowprintId};
+use std::collections::HashSet;
+use std::thread;
+use std::time::{Duration, UNIX_EPOCH};
+
+const JANUARY_1ST_2024_AS_MS: u64 = 1704096000000;
+const JANUARY_1ST_2024_AS_DURATION: Duration = Duration::from_millis(JANUARY_1ST_2024_AS_MS);
+
+fn snowprinter(
+    logical_volume_length: u64,
+    exhaustion_policy: ExhaustionPolicy,
+) -> (Snowprint<ManualClock>, ManualClock) {
+    let origin = UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION;
+    let clock = ManualClock::new(origin);
+    let settings = Settings {
+        origin_system_time: origin,
+        logical_volume_base: 4096,
+        logical_volume_length,
+        exhaustion_policy,
+        ..Default::default()
+    };
+    let snowprinter = Snowprint::with_clock(settings, clock.clone()).expect("valid settings");
+
+    (snowprinter, clock)
+}
+
+#[test]
+fn reserve_returns_a_range_across_logical_volumes() {
+    let (mut snowprinter, _clock) = snowprinter(4, ExhaustionPolicy::ReturnError);
+
+    let range = snowprinter.reserve(2000).expect("range should be reserved");
+    assert_eq!(range.len(), 2000);
+    assert_eq!(range.timestamp_ms(), 0);
+
+    let snowprints: Vec<SnowprintId> = range.collect();
+    assert_eq!(snowprints[0].logical_volume(), 4096);
+    assert_eq!(snowprints[0].sequence(), 1);
+    assert_eq!(snowprints[1999].logical_volume(), 4097);
+    assert_eq!(snowprints[1999].sequence(), 976);
+
+    // composing continues after the range
+    let snowprint = snowprinter.compose().expect("snowprint should compose");
+    assert_eq!(snowprint.logical_volume(), 4097);
+    assert_eq!(snowprint.sequence(), 977);
+}
+
+#[test]
+fn reserve_errors_when_length_exceeds_the_current_ms() {
+    let (mut snowprinter, clock) = snowprinter(1, ExhaustionPolicy::ReturnError);
+
+    let range = snowprinter.reserve(1024);
+    assert_eq!(range, Err(Error::ExceededAvailableSequences));
+
+    // nothing was consumed
+    let range = snowprinter.reserve(1023).expect("range should be reserved");
+    assert_eq!(range.len(), 1023);
+
+    clock.advance(Duration::from_millis(1));
+    let range = snowprinter.reserve(1024).expect("range should be reserved");
+    assert_eq!(range.timestamp_ms(), 1);
+}
+
+#[test]
+fn compose_batch_returns_unique_snowprints() {
+    let (mut snowprinter, _clock) = snowprinter(8, ExhaustionPolicy::ReturnError);
+
+    let snowprints = snowprinter
+        .compose_batch(5000)
+        .expect("batch should compose");
+    let unique: HashSet<SnowprintId> = snowprints.iter().copied().collect();
+    assert_eq!(unique.len(), 5000);
+    assert!(snowprints.windows(2).all(|pair| pair[0] < pair[1]));
+}
+
+#[test]
+fn fill_spills_into_later_ms_when_waiting() {
+    let (mut snowprinter, clock) = snowprinter(1, ExhaustionPolicy::Sleep);
+    let handle = thread::spawn(move || {
+        for _ in 0..2 {
+            thread::sleep(Duration::from_millis(20));
+            clock.advance(Duration::from_millis(1));
+        }
+    });
+
+    let mut snowprints = vec![SnowprintId::from(0); 3000];
+    snowprinter
+        .fill(&mut snowprints)
+        .expect("snowprints should fill");
+    handle.join().expect("thread should not panic");
+
+    let unique: HashSet<SnowprintId> = snowprints.iter().copied().collect();
+    assert_eq!(unique.len(), 3000);
+    assert_eq!(snowprints[0].timestamp_ms(), 0);
+    assert_eq!(snowprints[1023].timestamp_ms(), 1);
+    assert_eq!(snowprints[2999].timestamp_ms(), 2);
+}
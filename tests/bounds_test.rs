@@ -0,0 +1,103 @@
+use snowprints::{
+    compose, lower_bound, range_for, range_for_logical_volume, upper_bound, Error, ManualClock,
+    Settings, Snowprint,
+};
+use std::time::{Duration, UNIX_EPOCH};
+
+const JANUARY_1ST_2024_AS_MS: u64 = 1704096000000;
+const JANUARY_1ST_2024_AS_DURATION: Duration = Duration::from_millis(JANUARY_1ST_2024_AS_MS);
+
+#[test]
+fn bounds_cover_every_snowprint_in_a_ms() {
+    let origin_system_time = UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION;
+    let system_time = origin_system_time + Duration::from_micros(5500);
+
+    assert_eq!(
+        lower_bound(system_time, origin_system_time),
+        compose(5, 0, 0)
+    );
+    assert_eq!(
+        upper_bound(system_time, origin_system_time),
+        compose(5, 8191, 1023)
+    );
+    assert_eq!(
+        upper_bound(system_time, origin_system_time) + 1,
+        compose(6, 0, 0)
+    );
+}
+
+#[test]
+fn bounds_clamp_outside_the_timestamp() {
+    let origin_system_time = UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION;
+
+    assert_eq!(lower_bound(UNIX_EPOCH, origin_system_time), 0);
+    assert_eq!(
+        upper_bound(
+            origin_system_time + Duration::from_secs(u32::MAX as u64 * 1000),
+            origin_system_time
+        ),
+        u64::MAX
+    );
+}
+
+#[test]
+fn range_for_contains_snowprints_composed_in_the_window() {
+    let origin_system_time = UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION;
+    let settings = Settings {
+        origin_system_time,
+        logical_volume_base: 1024,
+        logical_volume_length: 1024,
+        ..Default::default()
+    };
+    let clock = ManualClock::new(origin_system_time + Duration::from_millis(9));
+    let mut snowprinter = Snowprint::with_clock(settings, clock.clone()).unwrap();
+
+    let start = origin_system_time + Duration::from_millis(10);
+    let end = origin_system_time + Duration::from_millis(20);
+    let range = range_for(start..end, origin_system_time);
+    assert_eq!(range, compose(10, 0, 0)..=compose(20, 0, 0) - 1);
+
+    let before = snowprinter.compose().unwrap();
+    assert!(!range.contains(&before.as_u64()));
+    for _ in 0..10 {
+        clock.advance(Duration::from_millis(1));
+        let snowprint = snowprinter.compose().unwrap();
+        assert!(range.contains(&snowprint.as_u64()));
+    }
+    clock.advance(Duration::from_millis(1));
+    let after = snowprinter.compose().unwrap();
+    assert!(!range.contains(&after.as_u64()));
+}
+
+#[test]
+fn range_for_an_empty_window_is_empty() {
+    let origin_system_time = UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION;
+    let start = origin_system_time + Duration::from_millis(10);
+
+    assert!(range_for(start..start, origin_system_time).is_empty());
+    assert!(range_for(start..origin_system_time, origin_system_time).is_empty());
+    assert!(range_for(UNIX_EPOCH..origin_system_time, origin_system_time).is_empty());
+    assert!(
+        range_for_logical_volume(start..start, origin_system_time, 7)
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[test]
+fn range_for_logical_volume_narrows_the_range() {
+    let origin_system_time = UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION;
+    let start = origin_system_time + Duration::from_millis(10);
+    let end = origin_system_time + Duration::from_millis(20);
+
+    assert_eq!(
+        range_for_logical_volume(start..end, origin_system_time, 7),
+        Ok(compose(10, 7, 0)..=compose(19, 7, 1023))
+    );
+    assert_eq!(
+        range_for_logical_volume(start..end, origin_system_time, 8192),
+        Err(Error::ExceededLogicalVolumeBitLength {
+            logical_volume: 8192
+        })
+    );
+}
@@ -0,0 +1,132 @@
+use snowprints::{compose, Encoding};
+use std::process::{Command, Output};
+
+const ORIGIN: &str = "2024-01-01T08:00:00Z";
+
+fn snowprints(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_snowprints"))
+        .args(args)
+        .output()
+        .expect("snowprints should run")
+}
+
+fn stdout(output: &Output) -> String {
+    assert!(output.status.success(), "{:?}", output);
+    String::from_utf8_lossy(&output.stdout)
+        .trim_end()
+        .to_string()
+}
+
+#[test]
+fn generate_composes_snowprints_within_settings() {
+    let output = snowprints(&[
+        "generate", "--origin", ORIGIN, "--base", "100", "--length", "4", "--count", "5",
+    ]);
+    let snowprints: Vec<u64> = stdout(&output)
+        .lines()
+        .map(|line| line.parse().expect("lines should be decimal"))
+        .collect();
+
+    assert_eq!(snowprints.len(), 5);
+    for pair in snowprints.windows(2) {
+        assert!(pair[0] < pair[1]);
+    }
+    for snowprint in snowprints {
+        let (_, logical_volume, _) = snowprints::decompose(snowprint);
+        assert!((100..104).contains(&logical_volume));
+    }
+}
+
+#[test]
+fn generate_prints_json_and_other_formats() {
+    let output = snowprints(&["generate", "--count", "2", "--format", "base62", "--json"]);
+    let json: Vec<String> = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(json.len(), 2);
+    assert!(json
+        .iter()
+        .all(|snowprint| Encoding::Base62.decode(snowprint).is_ok()));
+}
+
+#[test]
+fn decode_prints_the_fields_of_a_snowprint() {
+    let snowprint = compose(1500, 42, 7).to_string();
+    let output = snowprints(&["decode", &snowprint, "--origin", ORIGIN]);
+    let text = stdout(&output);
+
+    assert!(text.contains("created_at: 2024-01-01T08:00:01.500Z"));
+    assert!(text.contains("timestamp_ms: 1500"));
+    assert!(text.contains("logical_volume: 42"));
+    assert!(text.contains("sequence: 7"));
+
+    let output = snowprints(&["decode", &snowprint, "--origin", ORIGIN, "--json"]);
+    let json: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(json["id"], compose(1500, 42, 7));
+    assert_eq!(json["created_at"], "2024-01-01T08:00:01.500Z");
+    assert_eq!(json["logical_volume"], 42);
+    assert_eq!(json["sequence"], 7);
+}
+
+#[test]
+fn encode_and_decode_convert_between_formats() {
+    let snowprint = compose(1500, 42, 7);
+    let output = snowprints(&["encode", &snowprint.to_string()]);
+    let encoded = stdout(&output);
+    assert_eq!(encoded, Encoding::CrockfordBase32.encode(snowprint));
+
+    let output = snowprints(&["decode", &encoded, "--format", "base32", "--json"]);
+    let json: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(json["id"], snowprint);
+
+    let output = snowprints(&["encode", &snowprint.to_string(), "--format=base62"]);
+    assert_eq!(stdout(&output), Encoding::Base62.encode(snowprint));
+}
+
+#[test]
+fn bounds_prints_the_range_for_a_window() {
+    let output = snowprints(&[
+        "bounds",
+        "2024-01-01T08:00:00.010Z",
+        "2024-01-01T08:00:00.020Z",
+        "--origin",
+        ORIGIN,
+        "--json",
+    ]);
+    let json: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(json["lower_bound"], compose(10, 0, 0));
+    assert_eq!(json["upper_bound"], compose(20, 0, 0) - 1);
+
+    let output = snowprints(&[
+        "bounds",
+        "1704096000010",
+        "1704096000020",
+        "--origin",
+        ORIGIN,
+        "--volume",
+        "7",
+    ]);
+    let text = stdout(&output);
+    assert!(text.contains(&format!("lower_bound: {}", compose(10, 7, 0))));
+    assert!(text.contains(&format!("upper_bound: {}", compose(19, 7, 1023))));
+}
+
+#[test]
+fn mistakes_exit_with_an_error() {
+    let output = snowprints(&["bogus"]);
+    assert_eq!(output.status.code(), Some(2));
+
+    let output = snowprints(&["decode"]);
+    assert_eq!(output.status.code(), Some(2));
+
+    let output = snowprints(&["generate", "--count", "many"]);
+    assert_eq!(output.status.code(), Some(2));
+
+    let output = snowprints(&["generate", "--count", "4294967296"]);
+    assert_eq!(output.status.code(), Some(2));
+
+    let output = snowprints(&["generate", "--length", "0"]);
+    assert_eq!(output.status.code(), Some(1));
+
+    let output = snowprints(&["decode", "not-a-number"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(!output.stderr.is_empty());
+}
@@ -0,0 +1,114 @@
+use snowprints::{
+    decompose, Clock, ClockSkewHook, Error, ManualClock, MonotonicClock, Settings, Snowprint,
+    SyncSnowprint,
+};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const JANUARY_1ST_2024_AS_MS: u64 = 1704096000000;
+const JANUARY_1ST_2024_AS_DURATION: Duration = Duration::from_millis(JANUARY_1ST_2024_AS_MS);
+
+#[test]
+fn monotonic_clock_does_not_go_backwards() {
+    let clock = MonotonicClock::new();
+    let first = clock.now();
+    let second = clock.now();
+
+    assert!(first <= second);
+    assert!(first.duration_since(UNIX_EPOCH).is_ok());
+}
+
+#[test]
+fn manual_clock_exhausts_and_recovers_on_next_ms() {
+    let origin = UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION;
+    let clock = ManualClock::new(origin + Duration::from_millis(100));
+    let settings = Settings {
+        origin_system_time: origin,
+        logical_volume_base: 0,
+        logical_volume_length: 1,
+        ..Default::default()
+    };
+    let mut snowprinter = Snowprint::with_clock(settings, clock.clone()).expect("valid settings");
+
+    // sequence 0 of the first ms is reserved by Snowprint::with_clock
+    for sequence in 1..1024 {
+        let snowprint = snowprinter.compose().expect("sequences available");
+        assert_eq!(decompose(snowprint.into()), (100, 0, sequence));
+    }
+    assert_eq!(
+        snowprinter.compose(),
+        Err(Error::ExceededAvailableSequences)
+    );
+
+    clock.advance(Duration::from_millis(1));
+    let snowprint = snowprinter.compose().expect("next ms is available");
+    assert_eq!(decompose(snowprint.into()), (101, 0, 0));
+}
+
+#[test]
+fn manual_clock_drives_sync_snowprint() {
+    let origin = UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION;
+    let clock = ManualClock::new(origin);
+    let settings = Settings {
+        origin_system_time: origin,
+        logical_volume_base: 16,
+        logical_volume_length: 4,
+        ..Default::default()
+    };
+    let snowprinter = SyncSnowprint::with_clock(settings, clock.clone()).expect("valid settings");
+
+    clock.set(origin + Duration::from_millis(3));
+    let snowprint = snowprinter.compose().expect("snowprint should compose");
+    assert_eq!(decompose(snowprint.into()), (3, 17, 0));
+}
+
+#[test]
+fn manual_clock_before_origin_fails() {
+    let origin = SystemTime::now();
+    let clock = ManualClock::new(origin - Duration::from_secs(1));
+    let settings = Settings {
+        origin_system_time: origin,
+        logical_volume_base: 0,
+        logical_volume_length: 8192,
+        ..Default::default()
+    };
+
+    let snowprinter = Snowprint::with_clock(settings, clock);
+    assert_eq!(
+        snowprinter.err(),
+        Some(Error::FailedToParseOriginSystemTime)
+    );
+}
+
+#[test]
+fn snowprint_reports_clock_moving_backwards_beyond_tolerance() {
+    let origin = UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION;
+    let clock = ManualClock::new(origin + Duration::from_secs(60));
+    let alerts = Arc::new(AtomicU64::new(0));
+    let settings = Settings {
+        origin_system_time: origin,
+        max_backward_skew_ms: Some(1000),
+        clock_skew_hook: Some(ClockSkewHook::new({
+            let alerts = alerts.clone();
+            move |_by_ms| {
+                alerts.fetch_add(1, Ordering::Relaxed);
+            }
+        })),
+        ..Default::default()
+    };
+    let mut snowprinter = Snowprint::with_clock(settings, clock.clone()).expect("valid settings");
+
+    // a small step backwards keeps composing on the most recent ms
+    clock.rewind(Duration::from_millis(500));
+    let snowprint = snowprinter.compose().expect("skew is tolerated");
+    assert_eq!(snowprint.timestamp_ms(), 60000);
+
+    // a large step backwards is an error
+    clock.rewind(Duration::from_secs(5));
+    assert_eq!(
+        snowprinter.compose(),
+        Err(Error::ClockMovedBackwards { by_ms: 5500 })
+    );
+    assert_eq!(alerts.load(Ordering::Relaxed), 2);
+}
@@ -0,0 +1,140 @@
+use snowprints::{compose, Encoding, Error, SnowprintId};
+
+const ENCODINGS: [Encoding; 3] = [
+    Encoding::CrockfordBase32,
+    Encoding::Base62,
+    Encoding::Decimal,
+];
+
+#[test]
+fn encodings_have_fixed_widths() {
+    for encoding in ENCODINGS {
+        assert_eq!(encoding.encode(0).len(), encoding.width());
+        assert_eq!(encoding.encode(u64::MAX).len(), encoding.width());
+    }
+
+    assert_eq!(Encoding::CrockfordBase32.encode(0), "0000000000000");
+    assert_eq!(Encoding::CrockfordBase32.encode(u64::MAX), "FZZZZZZZZZZZZ");
+    assert_eq!(Encoding::Base62.encode(0), "00000000000");
+    assert_eq!(Encoding::Base62.encode(u64::MAX), "LygHa16AHYF");
+    assert_eq!(Encoding::Decimal.encode(42), "00000000000000000042");
+    assert_eq!(Encoding::Decimal.encode(u64::MAX), "18446744073709551615");
+}
+
+#[test]
+fn encodings_round_trip() {
+    let snowprints = [
+        0,
+        1,
+        31,
+        32,
+        61,
+        62,
+        compose(1234567890, 4321, 987),
+        u64::MAX - 1,
+        u64::MAX,
+    ];
+    for encoding in ENCODINGS {
+        for snowprint in snowprints {
+            assert_eq!(encoding.decode(&encoding.encode(snowprint)), Ok(snowprint));
+        }
+    }
+}
+
+#[test]
+fn string_order_matches_numeric_order() {
+    let mut snowprint: u64 = 1;
+    let mut snowprints = vec![0];
+    while let Some(next) = snowprint.checked_mul(7) {
+        snowprints.push(snowprint);
+        snowprints.push(snowprint + 1);
+        snowprint = next;
+    }
+    snowprints.push(u64::MAX);
+
+    for encoding in ENCODINGS {
+        for pair in snowprints.windows(2) {
+            assert!(encoding.encode(pair[0]) < encoding.encode(pair[1]));
+        }
+    }
+}
+
+#[test]
+fn crockford_base32_decodes_case_insensitively() {
+    let snowprint = compose(1234567890, 4321, 987);
+    let encoded = Encoding::CrockfordBase32.encode(snowprint);
+
+    assert_eq!(
+        Encoding::CrockfordBase32.decode(&encoded.to_lowercase()),
+        Ok(snowprint)
+    );
+    assert_eq!(Encoding::CrockfordBase32.decode("OOOOOOOOOOOOI"), Ok(1));
+    assert_eq!(Encoding::CrockfordBase32.decode("ooooooooooool"), Ok(1));
+}
+
+#[test]
+fn decode_errors() {
+    assert_eq!(
+        Encoding::CrockfordBase32.decode("000"),
+        Err(Error::InvalidEncodedLength {
+            encoding: Encoding::CrockfordBase32,
+            length: 3,
+        })
+    );
+    assert_eq!(
+        Encoding::CrockfordBase32.decode("000000000000U"),
+        Err(Error::InvalidEncodedCharacter {
+            encoding: Encoding::CrockfordBase32,
+            character: 'U',
+        })
+    );
+    assert_eq!(
+        Encoding::CrockfordBase32.decode("G000000000000"),
+        Err(Error::ExceededEncodedRange {
+            encoding: Encoding::CrockfordBase32,
+        })
+    );
+    assert_eq!(
+        Encoding::Base62.decode("0000000000-"),
+        Err(Error::InvalidEncodedCharacter {
+            encoding: Encoding::Base62,
+            character: '-',
+        })
+    );
+    assert_eq!(
+        Encoding::Base62.decode("LygHa16AHYG"),
+        Err(Error::ExceededEncodedRange {
+            encoding: Encoding::Base62,
+        })
+    );
+    assert_eq!(
+        Encoding::Decimal.decode("18446744073709551616"),
+        Err(Error::ExceededEncodedRange {
+            encoding: Encoding::Decimal,
+        })
+    );
+    assert_eq!(
+        Encoding::Decimal.decode("0000000000000000000é"),
+        Err(Error::InvalidEncodedCharacter {
+            encoding: Encoding::Decimal,
+            character: 'é',
+        })
+    );
+}
+
+#[test]
+fn snowprint_id_displays_and_parses_as_crockford_base32() {
+    let snowprint_id = SnowprintId::from(compose(1234567890, 4321, 987));
+    let encoded = snowprint_id.to_string();
+
+    assert_eq!(
+        encoded,
+        Encoding::CrockfordBase32.encode(snowprint_id.as_u64())
+    );
+    assert_eq!(encoded.parse::<SnowprintId>(), Ok(snowprint_id));
+    assert_eq!(
+        SnowprintId::decode(&snowprint_id.encode(Encoding::Base62), Encoding::Base62),
+        Ok(snowprint_id)
+    );
+    assert!("not a snowprint".parse::<SnowprintId>().is_err());
+}
@@ -0,0 +1,75 @@
+use snowprints::{decompose, Error, ExhaustionPolicy, ManualClock, Settings, Snowprint};
+use std::thread;
+use std::time::{Duration, UNIX_EPOCH};
+
+const JANUARY_1ST_2024_AS_MS: u64 = 1704096000000;
+const JANUARY_1ST_2024_AS_DURATION: Duration = Duration::from_millis(JANUARY_1ST_2024_AS_MS);
+
+fn exhausted_snowprinter(
+    exhaustion_policy: ExhaustionPolicy,
+) -> (Snowprint<ManualClock>, ManualClock) {
+    let origin = UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION;
+    let clock = ManualClock::new(origin);
+    let settings = Settings {
+        origin_system_time: origin,
+        logical_volume_base: 0,
+        logical_volume_length: 1,
+        exhaustion_policy,
+        ..Default::default()
+    };
+    let mut snowprinter = Snowprint::with_clock(settings, clock.clone()).expect("valid settings");
+    for _ in 1..1024 {
+        snowprinter.compose().expect("sequences available");
+    }
+
+    (snowprinter, clock)
+}
+
+fn advance_later(clock: &ManualClock) -> thread::JoinHandle<()> {
+    let clock = clock.clone();
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(20));
+        clock.advance(Duration::from_millis(1));
+    })
+}
+
+#[test]
+fn return_error_policy_returns_error() {
+    let (mut snowprinter, _clock) = exhausted_snowprinter(ExhaustionPolicy::ReturnError);
+    assert_eq!(
+        snowprinter.compose(),
+        Err(Error::ExceededAvailableSequences)
+    );
+}
+
+#[test]
+fn spin_policy_waits_for_next_ms() {
+    let (mut snowprinter, clock) = exhausted_snowprinter(ExhaustionPolicy::Spin);
+    let handle = advance_later(&clock);
+
+    let snowprint = snowprinter.compose().expect("next ms is available");
+    assert_eq!(decompose(snowprint.into()), (1, 0, 0));
+    handle.join().expect("thread should not panic");
+}
+
+#[test]
+fn sleep_policy_waits_for_next_ms() {
+    let (mut snowprinter, clock) = exhausted_snowprinter(ExhaustionPolicy::Sleep);
+    let handle = advance_later(&clock);
+
+    let snowprint = snowprinter.compose().expect("next ms is available");
+    assert_eq!(decompose(snowprint.into()), (1, 0, 0));
+    handle.join().expect("thread should not panic");
+}
+
+#[test]
+fn compose_blocking_waits_regardless_of_policy() {
+    let (mut snowprinter, clock) = exhausted_snowprinter(ExhaustionPolicy::ReturnError);
+    let handle = advance_later(&clock);
+
+    let snowprint = snowprinter
+        .compose_blocking()
+        .expect("next ms is available");
+    assert_eq!(decompose(snowprint.into()), (1, 0, 0));
+    handle.join().expect("thread should not panic");
+}
@@ -0,0 +1,381 @@
+#![cfg(feature = "grpc")]
+
+use snowprints::grpc::proto::snowprints_client::SnowprintsClient;
+use snowprints::grpc::proto::{DecodeRequest, LeaseRequest, NextBatchRequest, NextRequest};
+use snowprints::grpc::{GrpcConfig, GrpcServer};
+use snowprints::{compose, Settings, SnowprintId};
+use std::io;
+use std::process;
+use std::time::{Duration, UNIX_EPOCH};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tonic::transport::Channel;
+use tonic::Code;
+
+const JANUARY_1ST_2024_AS_MS: u64 = 1704096000000;
+const JANUARY_1ST_2024_AS_DURATION: Duration = Duration::from_millis(JANUARY_1ST_2024_AS_MS);
+
+fn config() -> GrpcConfig {
+    GrpcConfig {
+        address: "127.0.0.1:0".to_string(),
+        max_count: 100,
+        max_lease_ms: 1000,
+        lease_logical_volume_base: 2048,
+        lease_logical_volume_length: 8,
+        lease_store_path: None,
+        settings: Settings {
+            origin_system_time: UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION,
+            logical_volume_base: 1024,
+            logical_volume_length: 16,
+            ..Default::default()
+        },
+    }
+}
+
+// leases kept in a file are granted right away, memory waits out max_lease_ms
+fn leasing_config(name: &str) -> GrpcConfig {
+    let path = std::env::temp_dir().join(format!("snowprints-grpc-{}-{}", name, process::id()));
+    let _ = std::fs::remove_file(&path);
+    GrpcConfig {
+        lease_store_path: Some(path),
+        ..config()
+    }
+}
+
+async fn start(
+    config: GrpcConfig,
+) -> (
+    SnowprintsClient<Channel>,
+    oneshot::Sender<()>,
+    JoinHandle<io::Result<()>>,
+) {
+    let server = GrpcServer::bind(config).await.expect("server should bind");
+    let address = server.local_addr().unwrap();
+    let (shutdown, on_shutdown) = oneshot::channel::<()>();
+    let handle = tokio::spawn(server.run(async {
+        let _ = on_shutdown.await;
+    }));
+
+    let client = SnowprintsClient::connect(format!("http://{}", address))
+        .await
+        .expect("client should connect");
+    (client, shutdown, handle)
+}
+
+#[tokio::test]
+async fn next_returns_ids_on_settings_volumes() {
+    let (mut client, shutdown, handle) = start(config()).await;
+
+    let mut prev = 0;
+    for _ in 0..20 {
+        let id = client.next(NextRequest {}).await.unwrap().into_inner().id;
+        let logical_volume = SnowprintId::from(id).logical_volume();
+        assert!((1024..1040).contains(&logical_volume));
+        assert!(prev < id);
+        prev = id;
+    }
+
+    shutdown.send(()).unwrap();
+    handle.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn next_batch_returns_unique_ids() {
+    let (mut client, shutdown, handle) = start(config()).await;
+
+    let mut ids = client
+        .next_batch(NextBatchRequest { count: 100 })
+        .await
+        .unwrap()
+        .into_inner()
+        .ids;
+    assert_eq!(ids.len(), 100);
+    ids.sort_unstable();
+    ids.dedup();
+    assert_eq!(ids.len(), 100);
+
+    for count in [0, 101] {
+        let status = client
+            .next_batch(NextBatchRequest { count })
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+    }
+
+    shutdown.send(()).unwrap();
+    handle.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn decode_splits_an_id() {
+    let (mut client, shutdown, handle) = start(config()).await;
+
+    let id = compose(86_400_000, 7, 9);
+    let decoded = client
+        .decode(DecodeRequest { id })
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(decoded.id, id);
+    assert_eq!(decoded.created_at, "2024-01-02T08:00:00Z");
+    assert_eq!(decoded.timestamp_ms, 86_400_000);
+    assert_eq!(decoded.logical_volume, 7);
+    assert_eq!(decoded.sequence, 9);
+
+    shutdown.send(()).unwrap();
+    handle.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn lease_grants_disjoint_volumes_from_the_pool() {
+    let (mut client, shutdown, handle) = start(leasing_config(
+        "lease_grants_disjoint_volumes_from_the_pool",
+    ))
+    .await;
+
+    let mut first = client
+        .lease(LeaseRequest {
+            logical_volume_length: 3,
+            duration_ms: 1000,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    let mut second = client
+        .lease(LeaseRequest {
+            logical_volume_length: 5,
+            duration_ms: 1000,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+
+    let first_grant = first.message().await.unwrap().unwrap();
+    let second_grant = second.message().await.unwrap().unwrap();
+    assert_eq!(first_grant.logical_volume_base, 2048);
+    assert_eq!(first_grant.logical_volume_length, 3);
+    assert_eq!(second_grant.logical_volume_base, 2051);
+    assert_eq!(second_grant.logical_volume_length, 5);
+    assert_eq!(first_grant.origin_unix_ms, JANUARY_1ST_2024_AS_MS);
+
+    let status = client
+        .lease(LeaseRequest {
+            logical_volume_length: 1,
+            duration_ms: 1000,
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::ResourceExhausted);
+
+    shutdown.send(()).unwrap();
+    handle.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn lease_renews_while_the_stream_is_open() {
+    let (mut client, shutdown, handle) =
+        start(leasing_config("lease_renews_while_the_stream_is_open")).await;
+
+    let mut lease = client
+        .lease(LeaseRequest {
+            logical_volume_length: 2,
+            duration_ms: 40,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+
+    let granted = lease.message().await.unwrap().unwrap();
+    let mut renewed = granted;
+    for _ in 0..4 {
+        renewed = lease.message().await.unwrap().unwrap();
+    }
+    assert_eq!(renewed.logical_volume_base, granted.logical_volume_base);
+    assert!(granted.expires_at_unix_ms < renewed.expires_at_unix_ms);
+
+    shutdown.send(()).unwrap();
+    handle.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn lease_lapses_after_the_stream_closes() {
+    let (mut client, shutdown, handle) =
+        start(leasing_config("lease_lapses_after_the_stream_closes")).await;
+
+    let mut lease = client
+        .lease(LeaseRequest {
+            logical_volume_length: 8,
+            duration_ms: 20,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    lease.message().await.unwrap().unwrap();
+
+    let status = client
+        .lease(LeaseRequest {
+            logical_volume_length: 8,
+            duration_ms: 20,
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::ResourceExhausted);
+
+    drop(lease);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let mut lease = client
+        .lease(LeaseRequest {
+            logical_volume_length: 8,
+            duration_ms: 20,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    let grant = lease.message().await.unwrap().unwrap();
+    assert_eq!(grant.logical_volume_base, 2048);
+
+    shutdown.send(()).unwrap();
+    handle.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn lease_rejects_bad_requests() {
+    let (mut client, shutdown, handle) = start(config()).await;
+
+    let requests = [(0, 100), (9, 100), (1, 0), (1, 1001)];
+    for (logical_volume_length, duration_ms) in requests {
+        let status = client
+            .lease(LeaseRequest {
+                logical_volume_length,
+                duration_ms,
+            })
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+    }
+
+    shutdown.send(()).unwrap();
+    handle.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn shutdown_ends_open_leases() {
+    let (mut client, shutdown, handle) = start(leasing_config("shutdown_ends_open_leases")).await;
+
+    let mut lease = client
+        .lease(LeaseRequest {
+            logical_volume_length: 1,
+            duration_ms: 1000,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    lease.message().await.unwrap().unwrap();
+
+    shutdown.send(()).unwrap();
+    assert!(lease.message().await.unwrap().is_none());
+    handle.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn lease_in_a_file_survives_a_restart() {
+    let config = leasing_config("restart");
+    let (mut client, shutdown, handle) = start(config.clone()).await;
+
+    let mut lease = client
+        .lease(LeaseRequest {
+            logical_volume_length: 5,
+            duration_ms: 1000,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    let before = lease.message().await.unwrap().unwrap();
+    shutdown.send(()).unwrap();
+    handle.await.unwrap().unwrap();
+
+    // the old grant is still live, so only the rest of the pool is free
+    let (mut client, shutdown, handle) = start(config.clone()).await;
+    let mut lease = client
+        .lease(LeaseRequest {
+            logical_volume_length: 3,
+            duration_ms: 1000,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    let after = lease.message().await.unwrap().unwrap();
+    assert_eq!(before.logical_volume_base, 2048);
+    assert_eq!(after.logical_volume_base, 2053);
+
+    let status = client
+        .lease(LeaseRequest {
+            logical_volume_length: 1,
+            duration_ms: 1000,
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::ResourceExhausted);
+
+    shutdown.send(()).unwrap();
+    handle.await.unwrap().unwrap();
+    let _ = std::fs::remove_file(config.lease_store_path.unwrap());
+}
+
+#[tokio::test]
+async fn lease_in_memory_waits_out_grants_from_before_a_restart() {
+    let config = GrpcConfig {
+        max_lease_ms: 100,
+        ..config()
+    };
+    let request = LeaseRequest {
+        logical_volume_length: 8,
+        duration_ms: 100,
+    };
+
+    for _ in 0..2 {
+        let (mut client, shutdown, handle) = start(config.clone()).await;
+
+        let status = client.lease(request).await.unwrap_err();
+        assert_eq!(status.code(), Code::Unavailable);
+
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        let mut lease = client.lease(request).await.unwrap().into_inner();
+        lease.message().await.unwrap().unwrap();
+
+        shutdown.send(()).unwrap();
+        handle.await.unwrap().unwrap();
+    }
+}
+
+#[tokio::test]
+async fn bind_rejects_a_lease_pool_overlapping_settings() {
+    let mut overlapping = config();
+    overlapping.lease_logical_volume_base = 1030;
+
+    let err = GrpcServer::bind(overlapping).await.unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    assert!(err.to_string().contains("1030"));
+
+    let mut empty = config();
+    empty.lease_logical_volume_length = 0;
+    assert!(GrpcServer::bind(empty).await.is_err());
+
+    let mut overflowing = config();
+    overflowing.lease_logical_volume_base = u64::MAX;
+    let err = GrpcServer::bind(overflowing).await.unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn config_reads_json_with_defaults() {
+    let config: GrpcConfig =
+        serde_json::from_str(r#"{"address": "127.0.0.1:9000", "max_lease_ms": 5000}"#).unwrap();
+    assert_eq!(config.address, "127.0.0.1:9000");
+    assert_eq!(config.max_lease_ms, 5000);
+    assert_eq!(config.lease_logical_volume_base, 4096);
+    assert_eq!(config.lease_logical_volume_length, 4096);
+    assert_eq!(config.lease_store_path, None);
+    assert_eq!(config.settings.logical_volume_length, 4096);
+}
@@ -0,0 +1,386 @@
+use snowprints::{
+    Error, FileLeaseStore, LeasedSnowprint, ManualClock, MemoryLeaseStore, Settings, VolumeLease,
+    VolumeLeaseStore,
+};
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use std::thread;
+use std::time::{Duration, UNIX_EPOCH};
+
+const JANUARY_1ST_2024_AS_MS: u64 = 1704096000000;
+const JANUARY_1ST_2024_AS_DURATION: Duration = Duration::from_millis(JANUARY_1ST_2024_AS_MS);
+
+fn temp_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("snowprints-lease-{}-{}", name, process::id()));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+fn settings(logical_volume_length: u64) -> Settings {
+    Settings {
+        origin_system_time: UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION,
+        logical_volume_length,
+        ..Default::default()
+    }
+}
+
+fn clock() -> ManualClock {
+    ManualClock::new(UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION + Duration::from_secs(60))
+}
+
+// every store gets the same pool of 8 logical volumes starting at 100
+fn check_store(store: &mut impl VolumeLeaseStore) {
+    let first = store.acquire("first", 4, 100, 1000).unwrap();
+    assert_eq!(first.holder, "first");
+    assert_eq!(first.logical_volume_base, 100);
+    assert_eq!(first.logical_volume_length, 4);
+    assert_eq!(first.expires_at_ms, 1100);
+
+    let second = store.acquire("second", 3, 100, 1000).unwrap();
+    assert_eq!(second.logical_volume_base, 104);
+    assert_eq!(
+        store.acquire("third", 2, 100, 1000),
+        Err(Error::NoLogicalVolumesToLease {
+            logical_volume_length: 2
+        })
+    );
+
+    // released volumes are held until the released expiry, then the gap is filled first
+    store
+        .release(&VolumeLease {
+            expires_at_ms: 1001,
+            ..first.clone()
+        })
+        .unwrap();
+    assert_eq!(
+        store.acquire("third", 2, 99, 1000),
+        Err(Error::NoLogicalVolumesToLease {
+            logical_volume_length: 2
+        })
+    );
+    let third = store.acquire("third", 2, 99, 1001).unwrap();
+    assert_eq!(third.logical_volume_base, 100);
+
+    let second = store.heartbeat(&second, 500, 1050).unwrap();
+    assert_eq!(second.expires_at_ms, 1550);
+
+    // third expired at 1100, second is still held
+    assert_eq!(
+        store.heartbeat(&third, 100, 1100),
+        Err(Error::LeaseLapsed {
+            expires_at_ms: 1100
+        })
+    );
+    let fourth = store.acquire("fourth", 4, 100, 1100).unwrap();
+    assert_eq!(fourth.logical_volume_base, 100);
+    assert_eq!(
+        store.acquire("fifth", 2, 100, 1100),
+        Err(Error::NoLogicalVolumesToLease {
+            logical_volume_length: 2
+        })
+    );
+
+    // a stale copy of a lease can't renew volumes someone else holds now
+    assert!(matches!(
+        store.heartbeat(&first, 100, 1100),
+        Err(Error::LeaseLapsed { .. })
+    ));
+
+    assert_eq!(
+        store.acquire("sixth", 0, 100, 1100),
+        Err(Error::LogicalVolumeModuloIsZero)
+    );
+    assert_eq!(
+        store.acquire("sixth", 1, 0, 1100),
+        Err(Error::LeaseTtlIsZero)
+    );
+    assert_eq!(
+        store.acquire("", 1, 100, 1100),
+        Err(Error::InvalidLeaseHolder)
+    );
+    assert_eq!(
+        store.acquire("six\nth", 1, 100, 1100),
+        Err(Error::InvalidLeaseHolder)
+    );
+}
+
+#[test]
+fn memory_lease_store_leases_disjoint_volumes() {
+    check_store(&mut MemoryLeaseStore::new(100, 8));
+}
+
+#[test]
+fn file_lease_store_leases_disjoint_volumes() {
+    let path = temp_path("contract");
+    check_store(&mut FileLeaseStore::new(&path, 100, 8));
+
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn lease_store_rejects_pools_beyond_available_volumes() {
+    let mut store = MemoryLeaseStore::new(8000, 200);
+    assert_eq!(
+        store.acquire("first", 1, 100, 1000),
+        Err(Error::ExceededAvailableLogicalVolumes {
+            logical_volume_base: 8000,
+            logical_volume_length: 200,
+        })
+    );
+
+    let mut store = MemoryLeaseStore::new(u64::MAX, 2);
+    assert_eq!(
+        store.acquire("first", 1, 100, 1000),
+        Err(Error::ExceededAvailableLogicalVolumes {
+            logical_volume_base: u64::MAX,
+            logical_volume_length: 2,
+        })
+    );
+}
+
+#[test]
+fn lease_store_rejects_requests_that_overflow() {
+    let mut store = MemoryLeaseStore::new(0, 8);
+    assert_eq!(
+        store.acquire("first", u64::MAX, 100, 1000),
+        Err(Error::NoLogicalVolumesToLease {
+            logical_volume_length: u64::MAX
+        })
+    );
+    assert_eq!(
+        store.acquire("first", 1, u64::MAX, 1000),
+        Err(Error::ExceededLeaseTtl { ttl_ms: u64::MAX })
+    );
+
+    let lease = store.acquire("first", 1, 100, 1000).unwrap();
+    assert_eq!(
+        store.heartbeat(&lease, u64::MAX, 1050),
+        Err(Error::ExceededLeaseTtl { ttl_ms: u64::MAX })
+    );
+    assert_eq!(
+        store.heartbeat(&lease, 100, 1050).unwrap().expires_at_ms,
+        1150
+    );
+}
+
+#[test]
+fn file_lease_store_skips_past_oversized_leases() {
+    let path = temp_path("oversized");
+    fs::write(&path, format!("2 {} 5000 other\n", u64::MAX)).unwrap();
+
+    let mut store = FileLeaseStore::new(&path, 0, 4);
+    assert_eq!(
+        store
+            .acquire("first", 2, 100, 1000)
+            .unwrap()
+            .logical_volume_base,
+        0
+    );
+    assert_eq!(
+        store.acquire("second", 1, 100, 1000),
+        Err(Error::NoLogicalVolumesToLease {
+            logical_volume_length: 1
+        })
+    );
+
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn file_lease_store_is_shared_across_handles() {
+    let path = temp_path("shared");
+    let mut first_store = FileLeaseStore::new(&path, 0, 4);
+    let mut second_store = FileLeaseStore::new(&path, 0, 4);
+
+    let first = first_store.acquire("first host", 2, 100, 1000).unwrap();
+    let second = second_store.acquire("second host", 2, 100, 1000).unwrap();
+    assert_eq!(first.logical_volume_base, 0);
+    assert_eq!(second.logical_volume_base, 2);
+
+    let contents = fs::read_to_string(&path).unwrap();
+    assert_eq!(contents, "0 2 1100 first host\n2 2 1100 second host\n");
+
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn file_lease_store_locks_between_threads() {
+    let path = temp_path("threads");
+
+    let handles: Vec<_> = (0..8)
+        .map(|index| {
+            let mut store = FileLeaseStore::new(&path, 0, 64);
+            thread::spawn(move || {
+                store
+                    .acquire(&format!("holder-{}", index), 8, 10_000, 1000)
+                    .unwrap()
+            })
+        })
+        .collect();
+
+    let mut bases = HashSet::new();
+    for handle in handles {
+        let lease = handle.join().unwrap();
+        assert_eq!(lease.logical_volume_base % 8, 0);
+        assert!(bases.insert(lease.logical_volume_base));
+    }
+    assert_eq!(bases.len(), 8);
+
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn file_lease_store_rejects_a_corrupt_file() {
+    let path = temp_path("corrupt");
+    fs::write(&path, "not a lease\n").unwrap();
+
+    let mut store = FileLeaseStore::new(&path, 0, 4);
+    assert_eq!(
+        store.acquire("first", 1, 100, 1000),
+        Err(Error::FailedToAccessLeaseStore {
+            kind: std::io::ErrorKind::InvalidData
+        })
+    );
+
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn leased_snowprint_composes_on_its_leased_volumes() {
+    let store = MemoryLeaseStore::new(1024, 16);
+    let clock = clock();
+
+    let mut first =
+        LeasedSnowprint::with_clock(settings(8), store.clone(), "first", 1000, clock.clone())
+            .unwrap();
+    let mut second =
+        LeasedSnowprint::with_clock(settings(8), store.clone(), "second", 1000, clock.clone())
+            .unwrap();
+    assert_eq!(first.lease().logical_volume_base, 1024);
+    assert_eq!(second.lease().logical_volume_base, 1032);
+
+    let mut ids = HashSet::new();
+    for _ in 0..100 {
+        let snowprint = first.compose().unwrap();
+        assert!((1024..1032).contains(&snowprint.logical_volume()));
+        assert!(ids.insert(snowprint));
+
+        let snowprint = second.compose().unwrap();
+        assert!((1032..1040).contains(&snowprint.logical_volume()));
+        assert!(ids.insert(snowprint));
+
+        clock.advance(Duration::from_millis(1));
+    }
+
+    assert!(matches!(
+        LeasedSnowprint::with_clock(settings(1), store, "third", 1000, clock),
+        Err(Error::NoLogicalVolumesToLease { .. })
+    ));
+}
+
+#[test]
+fn leased_snowprint_heartbeats_while_composing() {
+    let clock = clock();
+    let mut snowprinter = LeasedSnowprint::with_clock(
+        settings(4),
+        MemoryLeaseStore::new(0, 4),
+        "first",
+        100,
+        clock.clone(),
+    )
+    .unwrap();
+    let expires_at_ms = snowprinter.lease().expires_at_ms;
+
+    // ten ttls later the lease is still held
+    for _ in 0..100 {
+        clock.advance(Duration::from_millis(10));
+        snowprinter.compose().unwrap();
+    }
+    assert!(expires_at_ms + 900 <= snowprinter.lease().expires_at_ms);
+    assert!(!snowprinter.is_lapsed());
+}
+
+#[test]
+fn leased_snowprint_stops_once_its_lease_lapses() {
+    let store = MemoryLeaseStore::new(0, 4);
+    let clock = clock();
+    let mut snowprinter =
+        LeasedSnowprint::with_clock(settings(4), store.clone(), "first", 100, clock.clone())
+            .unwrap();
+    snowprinter.compose().unwrap();
+    let expires_at_ms = snowprinter.lease().expires_at_ms;
+
+    clock.advance(Duration::from_millis(100));
+    assert_eq!(
+        snowprinter.compose(),
+        Err(Error::LeaseLapsed { expires_at_ms })
+    );
+    assert!(snowprinter.is_lapsed());
+
+    // the volumes went to someone else, the lapsed generator never comes back
+    let mut other =
+        LeasedSnowprint::with_clock(settings(4), store, "second", 100, clock.clone()).unwrap();
+    assert_eq!(other.lease().logical_volume_base, 0);
+    assert_eq!(
+        snowprinter.heartbeat(),
+        Err(Error::LeaseLapsed { expires_at_ms })
+    );
+    assert_eq!(
+        snowprinter.compose(),
+        Err(Error::LeaseLapsed { expires_at_ms })
+    );
+    assert!(other.compose().is_ok());
+}
+
+#[test]
+fn leased_snowprint_heartbeat_keeps_an_idle_lease() {
+    let clock = clock();
+    let mut snowprinter = LeasedSnowprint::with_clock(
+        settings(4),
+        MemoryLeaseStore::new(0, 4),
+        "first",
+        100,
+        clock.clone(),
+    )
+    .unwrap();
+
+    for _ in 0..10 {
+        clock.advance(Duration::from_millis(90));
+        snowprinter.heartbeat().unwrap();
+    }
+    assert!(snowprinter.compose().is_ok());
+}
+
+#[test]
+fn leased_snowprint_release_frees_its_volumes() {
+    let store = MemoryLeaseStore::new(0, 4);
+    let clock = clock();
+
+    let mut snowprinter =
+        LeasedSnowprint::with_clock(settings(4), store.clone(), "first", 100, clock.clone())
+            .unwrap();
+    assert!(
+        LeasedSnowprint::with_clock(settings(4), store.clone(), "second", 100, clock.clone())
+            .is_err()
+    );
+    let mut ids = HashSet::new();
+    for _ in 0..10 {
+        assert!(ids.insert(snowprinter.compose().unwrap()));
+    }
+    snowprinter.release().unwrap();
+
+    // the last ms the released generator composed on is still held
+    assert!(matches!(
+        LeasedSnowprint::with_clock(settings(4), store.clone(), "second", 100, clock.clone()),
+        Err(Error::NoLogicalVolumesToLease { .. })
+    ));
+
+    clock.advance(Duration::from_millis(1));
+    let mut other = LeasedSnowprint::with_clock(settings(4), store, "second", 100, clock).unwrap();
+    assert_eq!(other.lease().logical_volume_base, 0);
+    for _ in 0..10 {
+        assert!(ids.insert(other.compose().unwrap()));
+    }
+}
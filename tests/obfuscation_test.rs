@@ -0,0 +1,63 @@
+#![cfg(feature = "obfuscation")]
+
+use snowprints::{compose, Obfuscator, SnowprintId};
+use std::collections::HashSet;
+
+const KEY: u128 = 0x0123456789abcdef_fedcba9876543210;
+
+#[test]
+fn obfuscated_snowprints_reveal_the_original() {
+    let obfuscator = Obfuscator::new(KEY);
+    for snowprint in [0, 1, compose(1234567890, 4321, 987), u64::MAX] {
+        assert_eq!(
+            obfuscator.reveal(obfuscator.obfuscate(snowprint)),
+            snowprint
+        );
+    }
+
+    let snowprint_id = SnowprintId::from(compose(1234567890, 4321, 987));
+    assert_eq!(
+        obfuscator.reveal_id(obfuscator.obfuscate_id(snowprint_id)),
+        snowprint_id
+    );
+}
+
+#[test]
+fn neighboring_snowprints_do_not_collide() {
+    let obfuscator = Obfuscator::new(KEY);
+    let mut obfuscated = HashSet::new();
+    for timestamp_ms in 0..8 {
+        for logical_volume in 0..16 {
+            for sequence in 0..64 {
+                let snowprint = compose(timestamp_ms, logical_volume, sequence);
+                assert!(obfuscated.insert(obfuscator.obfuscate(snowprint)));
+            }
+        }
+    }
+}
+
+#[test]
+fn obfuscation_hides_order_and_depends_on_the_key() {
+    let obfuscator = Obfuscator::new(KEY);
+    let snowprint = compose(1234567890, 4321, 987);
+
+    let obfuscated = obfuscator.obfuscate(snowprint);
+    assert_ne!(obfuscated, snowprint);
+    assert_ne!(obfuscator.obfuscate(snowprint + 1), obfuscated + 1);
+    assert_ne!(Obfuscator::new(KEY + 1).obfuscate(snowprint), obfuscated);
+}
+
+#[test]
+fn obfuscation_is_stable() {
+    let obfuscator = Obfuscator::new(KEY);
+    assert_eq!(obfuscator.obfuscate(0), 10738990771711501003);
+    assert_eq!(
+        obfuscator.obfuscate(compose(1234567890, 4321, 987)),
+        7161846470318420927
+    );
+}
+
+#[test]
+fn obfuscator_debug_does_not_show_keys() {
+    assert_eq!(format!("{:?}", Obfuscator::new(KEY)), "Obfuscator");
+}
@@ -0,0 +1,214 @@
+use snowprints::{
+    Error, ExhaustionPolicy, FileStateStore, ManualClock, PersistentSnowprint, Settings, StateStore,
+};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::process;
+use std::thread;
+use std::time::{Duration, UNIX_EPOCH};
+
+const JANUARY_1ST_2024_AS_MS: u64 = 1704096000000;
+const JANUARY_1ST_2024_AS_DURATION: Duration = Duration::from_millis(JANUARY_1ST_2024_AS_MS);
+
+fn temp_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("snowprints-{}-{}", name, process::id()));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+fn settings(exhaustion_policy: ExhaustionPolicy) -> Settings {
+    Settings {
+        origin_system_time: UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION,
+        logical_volume_base: 0,
+        logical_volume_length: 8192,
+        exhaustion_policy,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn file_state_store_loads_what_it_stores() {
+    let path = temp_path("round-trip");
+    let mut store = FileStateStore::new(&path);
+
+    assert_eq!(store.load_high_water_mark(), Ok(None));
+    assert_eq!(store.store_high_water_mark(987654321), Ok(()));
+    assert_eq!(store.load_high_water_mark(), Ok(Some(987654321)));
+
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn file_state_store_rejects_corrupt_marks() {
+    let path = temp_path("corrupt");
+    fs::write(&path, "not a number").expect("file should be written");
+
+    let mut store = FileStateStore::new(&path);
+    assert_eq!(
+        store.load_high_water_mark(),
+        Err(Error::FailedToLoadHighWaterMark {
+            kind: ErrorKind::InvalidData
+        })
+    );
+
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn persistent_snowprint_refuses_a_clock_behind_the_mark() {
+    let path = temp_path("refuses");
+    let origin = UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION;
+    let clock = ManualClock::new(origin + Duration::from_millis(500));
+
+    let mut snowprinter = PersistentSnowprint::with_clock(
+        settings(ExhaustionPolicy::ReturnError),
+        FileStateStore::new(&path),
+        clock.clone(),
+    )
+    .expect("no mark yet");
+    let snowprint = snowprinter.compose().expect("snowprint should compose");
+    assert_eq!(snowprint.timestamp_ms(), 500);
+    assert_eq!(snowprinter.high_water_mark(), Some(1500));
+
+    // restart without persisting after the clock was stepped backwards
+    clock.rewind(Duration::from_millis(200));
+    let restarted = PersistentSnowprint::with_clock(
+        settings(ExhaustionPolicy::ReturnError),
+        FileStateStore::new(&path),
+        clock.clone(),
+    );
+    assert_eq!(
+        restarted.err(),
+        Some(Error::ClockBehindHighWaterMark {
+            duration_ms: 300,
+            high_water_mark: 1500,
+        })
+    );
+
+    // the mark itself may already have been used
+    clock.advance(Duration::from_millis(1200));
+    let restarted = PersistentSnowprint::with_clock(
+        settings(ExhaustionPolicy::ReturnError),
+        FileStateStore::new(&path),
+        clock.clone(),
+    );
+    assert_eq!(
+        restarted.err(),
+        Some(Error::ClockBehindHighWaterMark {
+            duration_ms: 1500,
+            high_water_mark: 1500,
+        })
+    );
+
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn persistent_snowprint_waits_for_the_clock_to_pass_the_mark() {
+    let path = temp_path("waits");
+    let origin = UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION;
+    let clock = ManualClock::new(origin + Duration::from_millis(500));
+
+    let mut store = FileStateStore::new(&path);
+    store
+        .store_high_water_mark(501)
+        .expect("mark should be stored");
+
+    let handle = thread::spawn({
+        let clock = clock.clone();
+        move || {
+            thread::sleep(Duration::from_millis(20));
+            clock.advance(Duration::from_millis(2));
+        }
+    });
+    let mut snowprinter =
+        PersistentSnowprint::with_clock(settings(ExhaustionPolicy::Sleep), store, clock.clone())
+            .expect("clock should pass the mark");
+    handle.join().expect("thread should not panic");
+
+    let snowprint = snowprinter.compose().expect("snowprint should compose");
+    assert_eq!(snowprint.timestamp_ms(), 502);
+
+    let mut store = snowprinter.into_store();
+    assert_eq!(store.load_high_water_mark(), Ok(Some(1502)));
+
+    let _ = fs::remove_file(&path);
+}
+
+// keeps the mark in memory and counts how often it is stored
+#[derive(Debug, Default)]
+struct CountingStore {
+    high_water_mark: Option<u64>,
+    stores: usize,
+}
+
+impl StateStore for CountingStore {
+    fn load_high_water_mark(&mut self) -> Result<Option<u64>, Error> {
+        Ok(self.high_water_mark)
+    }
+
+    fn store_high_water_mark(&mut self, duration_ms: u64) -> Result<(), Error> {
+        self.high_water_mark = Some(duration_ms);
+        self.stores += 1;
+        Ok(())
+    }
+}
+
+#[test]
+fn persistent_snowprint_stores_the_mark_ahead_of_the_clock() {
+    let origin = UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION;
+    let clock = ManualClock::new(origin + Duration::from_millis(500));
+
+    let mut snowprinter = PersistentSnowprint::with_clock(
+        settings(ExhaustionPolicy::ReturnError),
+        CountingStore::default(),
+        clock.clone(),
+    )
+    .expect("no mark yet");
+
+    // every ms up to the reserved mark shares one store
+    for _ in 0..1001 {
+        snowprinter.compose().expect("snowprint should compose");
+        clock.advance(Duration::from_millis(1));
+    }
+    assert_eq!(snowprinter.high_water_mark(), Some(1500));
+    snowprinter.compose().expect("snowprint should compose");
+    assert_eq!(snowprinter.high_water_mark(), Some(2501));
+
+    let store = snowprinter.into_store();
+    assert_eq!(store.stores, 2);
+}
+
+#[test]
+fn persistent_snowprint_persists_the_last_ms_for_a_clean_restart() {
+    let path = temp_path("persists");
+    let origin = UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION;
+    let clock = ManualClock::new(origin + Duration::from_millis(500));
+
+    let mut snowprinter = PersistentSnowprint::with_clock(
+        settings(ExhaustionPolicy::ReturnError),
+        FileStateStore::new(&path),
+        clock.clone(),
+    )
+    .expect("no mark yet");
+    snowprinter.compose().expect("snowprint should compose");
+    assert_eq!(snowprinter.persist(), Ok(()));
+    assert_eq!(snowprinter.high_water_mark(), Some(500));
+
+    // composing after persisting reserves again
+    clock.advance(Duration::from_millis(1));
+    snowprinter.compose().expect("snowprint should compose");
+    assert_eq!(snowprinter.high_water_mark(), Some(1501));
+    assert_eq!(snowprinter.persist(), Ok(()));
+
+    let mut store = snowprinter.into_store();
+    assert_eq!(store.load_high_water_mark(), Ok(Some(501)));
+
+    clock.advance(Duration::from_millis(1));
+    let restarted =
+        PersistentSnowprint::with_clock(settings(ExhaustionPolicy::ReturnError), store, clock);
+    assert!(restarted.is_ok());
+
+    let _ = fs::remove_file(&path);
+}
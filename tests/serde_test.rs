@@ -0,0 +1,208 @@
+#![cfg(feature = "serde")]
+
+use serde::{Deserialize, Serialize};
+use snowprints::{
+    Error, ExhaustionPolicy, LogicalVolumeSet, ManualClock, Settings, ShardMap, Snowprint,
+    SnowprintId, StateSnapshot, WeightedLogicalVolume,
+};
+use std::time::{Duration, UNIX_EPOCH};
+
+const JANUARY_1ST_2024_AS_MS: u64 = 1704096000000;
+const JANUARY_1ST_2024_AS_DURATION: Duration = Duration::from_millis(JANUARY_1ST_2024_AS_MS);
+
+#[derive(Debug, Eq, PartialEq, Serialize, Deserialize)]
+struct Record {
+    #[serde(with = "snowprints::serialization::id_as_string")]
+    id: SnowprintId,
+}
+
+#[test]
+fn snowprint_id_serializes_as_a_number() {
+    let snowprint_id = SnowprintId::from(9007199254740993);
+    let json = serde_json::to_string(&snowprint_id).unwrap();
+    assert_eq!(json, "9007199254740993");
+    assert_eq!(
+        serde_json::from_str::<SnowprintId>(&json).unwrap(),
+        snowprint_id
+    );
+}
+
+#[test]
+fn snowprint_id_serializes_as_a_string() {
+    let record = Record {
+        id: SnowprintId::from(9007199254740993),
+    };
+    let json = serde_json::to_string(&record).unwrap();
+    assert_eq!(json, r#"{"id":"9007199254740993"}"#);
+    assert_eq!(serde_json::from_str::<Record>(&json).unwrap(), record);
+
+    let from_number: Record = serde_json::from_str(r#"{"id":9007199254740993}"#).unwrap();
+    assert_eq!(from_number, record);
+
+    assert!(serde_json::from_str::<Record>(r#"{"id":"not a number"}"#).is_err());
+}
+
+#[test]
+fn settings_round_trip() {
+    let settings = Settings {
+        origin_system_time: UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION,
+        logical_volume_base: 16,
+        logical_volume_length: 64,
+        exhaustion_policy: ExhaustionPolicy::Sleep,
+        max_backward_skew_ms: Some(250),
+        ..Default::default()
+    };
+
+    let json = serde_json::to_string(&settings).unwrap();
+    assert_eq!(
+        json,
+        r#"{"origin_system_time":"2024-01-01T08:00:00Z","logical_volume_base":16,"logical_volume_length":64,"exhaustion_policy":"sleep","max_backward_skew_ms":250,"strictly_increasing":false}"#
+    );
+    assert_eq!(serde_json::from_str::<Settings>(&json).unwrap(), settings);
+}
+
+#[test]
+fn settings_fill_missing_fields_with_defaults() {
+    let settings: Settings =
+        serde_json::from_str(r#"{"origin_system_time":1704096000000,"logical_volume_length":64}"#)
+            .unwrap();
+
+    assert_eq!(
+        settings,
+        Settings {
+            origin_system_time: UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION,
+            logical_volume_length: 64,
+            ..Default::default()
+        }
+    );
+
+    let settings: Settings =
+        serde_json::from_str(r#"{"origin_system_time":"2024-01-01T00:00:00-08:00"}"#).unwrap();
+    assert_eq!(
+        settings.origin_system_time,
+        UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION
+    );
+
+    assert!(serde_json::from_str::<Settings>(r#"{"origin_system_time":"yesterday"}"#).is_err());
+}
+
+#[test]
+fn snowprint_resumes_from_a_snapshot() {
+    let settings = Settings {
+        origin_system_time: UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION,
+        logical_volume_base: 0,
+        logical_volume_length: 4,
+        ..Default::default()
+    };
+    let clock = ManualClock::new(UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION);
+
+    let mut snowprint = Snowprint::with_clock(settings.clone(), clock.clone()).unwrap();
+    let mut prev_id = snowprint.compose().unwrap();
+    for _ in 0..5 {
+        prev_id = snowprint.compose().unwrap();
+    }
+
+    let json = serde_json::to_string(&snowprint.snapshot()).unwrap();
+    let snapshot: StateSnapshot = serde_json::from_str(&json).unwrap();
+    assert_eq!(snapshot, snowprint.snapshot());
+
+    // the snapshot's ms may have used any sequence, so the resumed generator waits it out
+    let mut resumed = Snowprint::from_snapshot(settings.clone(), snapshot, clock.clone()).unwrap();
+    assert_eq!(resumed.compose(), Err(Error::ExceededAvailableSequences));
+    clock.advance(Duration::from_millis(1));
+    let next_id = resumed.compose().unwrap();
+    assert!(prev_id < next_id);
+
+    let snapshot = StateSnapshot {
+        logical_volume: 4,
+        ..snapshot
+    };
+    let resumed = Snowprint::from_snapshot(settings, snapshot, ManualClock::new(UNIX_EPOCH));
+    assert_eq!(resumed.err(), Some(Error::InvalidStateSnapshot));
+}
+
+#[test]
+fn snowprint_snapshot_covers_keyed_snowprints() {
+    for strictly_increasing in [false, true] {
+        check_snapshot_covers_keyed_snowprints(Settings {
+            origin_system_time: UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION,
+            logical_volume_base: 0,
+            logical_volume_length: 4,
+            strictly_increasing,
+            ..Default::default()
+        });
+    }
+}
+
+fn check_snapshot_covers_keyed_snowprints(settings: Settings) {
+    let clock = ManualClock::new(UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION);
+
+    let mut snowprint = Snowprint::with_clock(settings.clone(), clock.clone()).unwrap();
+    clock.advance(Duration::from_millis(101));
+    let rotated = snowprint.compose().unwrap();
+    clock.advance(Duration::from_millis(1));
+    let keyed = snowprint.compose_for_key("tenant").unwrap();
+    let snapshot = snowprint.snapshot();
+    assert_eq!(snapshot.prev_duration_ms, 102);
+
+    // neither the rotation nor a key can compose on ms 102 again
+    let mut resumed = Snowprint::from_snapshot(settings, snapshot, clock.clone()).unwrap();
+    assert_eq!(resumed.compose(), Err(Error::ExceededAvailableSequences));
+    assert_eq!(
+        resumed.compose_for_key("tenant"),
+        Err(Error::ExceededAvailableSequences)
+    );
+    assert_eq!(
+        resumed.compose_child_of(rotated),
+        Err(Error::ExceededAvailableSequences)
+    );
+
+    clock.advance(Duration::from_millis(1));
+    let next = resumed.compose().unwrap();
+    assert!(keyed < next);
+    assert_eq!(next.timestamp_ms(), 103);
+    assert_eq!(
+        resumed.compose_for_key("tenant").unwrap().timestamp_ms(),
+        103
+    );
+}
+
+#[test]
+fn shard_map_round_trip() {
+    let shard_map = ShardMap::uniform(&["a", "b"]).unwrap();
+
+    let json = serde_json::to_string(&shard_map).unwrap();
+    assert_eq!(
+        json,
+        r#"[{"logical_volume_base":0,"logical_volume_length":4096,"shard":"a"},{"logical_volume_base":4096,"logical_volume_length":4096,"shard":"b"}]"#
+    );
+    assert_eq!(serde_json::from_str::<ShardMap>(&json).unwrap(), shard_map);
+
+    let overlapping = r#"[{"logical_volume_base":0,"logical_volume_length":10,"shard":"a"},{"logical_volume_base":5,"logical_volume_length":10,"shard":"b"}]"#;
+    assert!(serde_json::from_str::<ShardMap>(overlapping).is_err());
+}
+
+#[test]
+fn settings_read_logical_volume_sets() {
+    let settings: Settings = serde_json::from_str(
+        r#"{"logical_volume_set":[{"logical_volume":3,"weight":2},{"logical_volume":70}]}"#,
+    )
+    .unwrap();
+    let logical_volume_set = LogicalVolumeSet::weighted([
+        WeightedLogicalVolume {
+            logical_volume: 3,
+            weight: 2,
+        },
+        WeightedLogicalVolume {
+            logical_volume: 70,
+            weight: 1,
+        },
+    ]);
+    assert_eq!(settings.logical_volume_set, Some(logical_volume_set));
+
+    let json = serde_json::to_string(&settings).unwrap();
+    assert!(json.contains(
+        r#""logical_volume_set":[{"logical_volume":3,"weight":2},{"logical_volume":70,"weight":1}]"#
+    ));
+    assert_eq!(serde_json::from_str::<Settings>(&json).unwrap(), settings);
+}
@@ -0,0 +1,191 @@
+#![cfg(feature = "server")]
+
+use snowprints::server::{Server, ServerConfig};
+use snowprints::{compose, FileStateStore, Settings, SnowprintId, StateStore};
+use std::fs;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::path::PathBuf;
+use std::process;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+const JANUARY_1ST_2024_AS_MS: u64 = 1704096000000;
+const JANUARY_1ST_2024_AS_DURATION: Duration = Duration::from_millis(JANUARY_1ST_2024_AS_MS);
+
+fn temp_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("snowprints-{}-{}", name, process::id()));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+fn config(name: &str) -> ServerConfig {
+    ServerConfig {
+        address: "127.0.0.1:0".to_string(),
+        high_water_mark_path: temp_path(name),
+        max_count: 100,
+        settings: Settings {
+            origin_system_time: UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION,
+            logical_volume_base: 1024,
+            logical_volume_length: 16,
+            ..Default::default()
+        },
+    }
+}
+
+fn start(config: ServerConfig) -> (SocketAddr, impl FnOnce(), JoinHandle<std::io::Result<()>>) {
+    let server = Server::bind(config).expect("server should bind");
+    let address = server.local_addr().unwrap();
+    let shutdown_handle = server.shutdown_handle();
+    let handle = thread::spawn(move || server.run());
+    (address, move || shutdown_handle.shutdown(), handle)
+}
+
+fn request(address: SocketAddr, request: &str) -> (u16, serde_json::Value) {
+    let mut stream = TcpStream::connect(address).unwrap();
+    stream.write_all(request.as_bytes()).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+    assert!(head.contains("Content-Type: application/json"));
+    (status, serde_json::from_str(body).unwrap())
+}
+
+fn get(address: SocketAddr, target: &str) -> (u16, serde_json::Value) {
+    request(
+        address,
+        &format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", target),
+    )
+}
+
+fn parse_id(value: &serde_json::Value) -> SnowprintId {
+    SnowprintId::from(value.as_str().unwrap().parse::<u64>().unwrap())
+}
+
+#[test]
+fn server_composes_ids() {
+    let config = config("server-id");
+    let path = config.high_water_mark_path.clone();
+    let (address, shutdown, handle) = start(config);
+
+    let (status, body) = get(address, "/id");
+    assert_eq!(status, 200);
+    let first = parse_id(&body["id"]);
+    assert!((1024..1040).contains(&first.logical_volume()));
+
+    let (status, body) = get(address, "/ids?count=5");
+    assert_eq!(status, 200);
+    let ids: Vec<SnowprintId> = body["ids"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|id| parse_id(&id["id"]))
+        .collect();
+    assert_eq!(ids.len(), 5);
+    assert!(first < ids[0]);
+    for pair in ids.windows(2) {
+        assert!(pair[0] < pair[1]);
+    }
+
+    shutdown();
+    handle.join().unwrap().unwrap();
+
+    let high_water_mark = FileStateStore::new(&path).load_high_water_mark().unwrap();
+    assert_eq!(high_water_mark, Some(ids[4].timestamp_ms()));
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn server_decodes_ids() {
+    let config = config("server-decode");
+    let path = config.high_water_mark_path.clone();
+    let (address, shutdown, handle) = start(config);
+
+    let snowprint = SnowprintId::from(compose(1500, 42, 7));
+    for id in [snowprint.as_u64().to_string(), snowprint.to_string()] {
+        let (status, body) = get(address, &format!("/decode/{}", id));
+        assert_eq!(status, 200);
+        assert_eq!(body["id"], snowprint.as_u64().to_string());
+        assert_eq!(body["created_at"], "2024-01-01T08:00:01.500Z");
+        assert_eq!(body["timestamp_ms"], 1500);
+        assert_eq!(body["logical_volume"], 42);
+        assert_eq!(body["sequence"], 7);
+    }
+
+    shutdown();
+    handle.join().unwrap().unwrap();
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn server_rejects_bad_requests() {
+    let config = config("server-bad-requests");
+    let path = config.high_water_mark_path.clone();
+    let (address, shutdown, handle) = start(config);
+
+    assert_eq!(get(address, "/nowhere").0, 404);
+    assert_eq!(get(address, "/ids?count=0").0, 400);
+    assert_eq!(get(address, "/ids?count=101").0, 400);
+    assert_eq!(get(address, "/ids?count=many").0, 400);
+    assert_eq!(get(address, "/decode/not-an-id").0, 400);
+    assert_eq!(
+        request(address, "POST /id HTTP/1.1\r\nContent-Length: 0\r\n\r\n").0,
+        405
+    );
+    assert_eq!(request(address, "nonsense\r\n\r\n").0, 400);
+
+    // still serving after bad requests
+    assert_eq!(get(address, "/id").0, 200);
+
+    shutdown();
+    handle.join().unwrap().unwrap();
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn server_answers_while_a_connection_sits_idle() {
+    let config = config("server-idle");
+    let path = config.high_water_mark_path.clone();
+    let (address, shutdown, handle) = start(config);
+
+    // a client that connects and never sends a request
+    let idle = TcpStream::connect(address).unwrap();
+    thread::sleep(Duration::from_millis(50));
+
+    let started = Instant::now();
+    assert_eq!(get(address, "/id").0, 200);
+    assert!(started.elapsed() < Duration::from_secs(1));
+
+    drop(idle);
+    shutdown();
+    handle.join().unwrap().unwrap();
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn server_config_reads_json_with_defaults() {
+    let path = temp_path("server-config");
+    fs::write(
+        &path,
+        r#"{"address":"127.0.0.1:9000","settings":{"origin_system_time":"2024-01-01T08:00:00Z","logical_volume_length":16}}"#,
+    )
+    .unwrap();
+
+    let config = ServerConfig::from_file(&path).unwrap();
+    assert_eq!(config.address, "127.0.0.1:9000");
+    assert_eq!(config.max_count, ServerConfig::default().max_count);
+    assert_eq!(
+        config.settings.origin_system_time,
+        UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION
+    );
+    assert_eq!(config.settings.logical_volume_length, 16);
+
+    fs::write(&path, "not json").unwrap();
+    assert_eq!(
+        ServerConfig::from_file(&path).unwrap_err().kind(),
+        std::io::ErrorKind::InvalidData
+    );
+    let _ = fs::remove_file(&path);
+}
@@ -0,0 +1,155 @@
+use snowprints::{compose, Error, ShardMap, ShardMove, ShardRange, SnowprintId};
+
+fn shard_range(logical_volume_base: u64, logical_volume_length: u64, shard: &str) -> ShardRange {
+    ShardRange {
+        logical_volume_base,
+        logical_volume_length,
+        shard: shard.to_string(),
+    }
+}
+
+fn shard_move(
+    logical_volume_base: u64,
+    logical_volume_length: u64,
+    from: Option<&str>,
+    to: Option<&str>,
+) -> ShardMove {
+    ShardMove {
+        logical_volume_base,
+        logical_volume_length,
+        from: from.map(String::from),
+        to: to.map(String::from),
+    }
+}
+
+#[test]
+fn uniform_splits_every_logical_volume() {
+    let shard_map = ShardMap::uniform(&["a", "b", "c"]).unwrap();
+    assert_eq!(
+        shard_map.ranges(),
+        [
+            shard_range(0, 2730, "a"),
+            shard_range(2730, 2731, "b"),
+            shard_range(5461, 2731, "c"),
+        ]
+    );
+
+    assert_eq!(ShardMap::uniform(&[]), Ok(ShardMap::new()));
+    assert!(ShardMap::uniform(&["a"; 8193]).is_err());
+}
+
+#[test]
+fn shard_for_looks_up_the_logical_volume() {
+    let mut shard_map = ShardMap::new();
+    shard_map.assign(0, 100, "a").unwrap();
+    shard_map.assign(200, 100, "b").unwrap();
+
+    assert_eq!(shard_map.shard_for_logical_volume(0), Some("a"));
+    assert_eq!(shard_map.shard_for_logical_volume(99), Some("a"));
+    assert_eq!(shard_map.shard_for_logical_volume(100), None);
+    assert_eq!(shard_map.shard_for_logical_volume(250), Some("b"));
+    assert_eq!(shard_map.shard_for_logical_volume(300), None);
+
+    let snowprint_id = SnowprintId::from(compose(1234567890, 250, 987));
+    assert_eq!(shard_map.shard_for(snowprint_id), Some("b"));
+}
+
+#[test]
+fn assign_splits_and_merges_ranges() {
+    let mut shard_map = ShardMap::uniform(&["a", "b"]).unwrap();
+
+    shard_map.assign(1000, 5000, "c").unwrap();
+    assert_eq!(
+        shard_map.ranges(),
+        [
+            shard_range(0, 1000, "a"),
+            shard_range(1000, 5000, "c"),
+            shard_range(6000, 2192, "b"),
+        ]
+    );
+
+    shard_map.assign(1000, 5000, "a").unwrap();
+    assert_eq!(
+        shard_map.ranges(),
+        [shard_range(0, 6000, "a"), shard_range(6000, 2192, "b")]
+    );
+
+    shard_map.unassign(10, 20).unwrap();
+    assert_eq!(
+        shard_map.ranges(),
+        [
+            shard_range(0, 10, "a"),
+            shard_range(30, 5970, "a"),
+            shard_range(6000, 2192, "b"),
+        ]
+    );
+    assert_eq!(shard_map.shard_for_logical_volume(15), None);
+}
+
+#[test]
+fn assign_rejects_invalid_ranges() {
+    let mut shard_map = ShardMap::new();
+    assert_eq!(
+        shard_map.assign(0, 0, "a"),
+        Err(Error::LogicalVolumeModuloIsZero)
+    );
+    assert_eq!(
+        shard_map.assign(8000, 200, "a"),
+        Err(Error::ExceededAvailableLogicalVolumes {
+            logical_volume_base: 8000,
+            logical_volume_length: 200,
+        })
+    );
+    assert_eq!(
+        shard_map.assign(u64::MAX, 1, "a"),
+        Err(Error::ExceededAvailableLogicalVolumes {
+            logical_volume_base: u64::MAX,
+            logical_volume_length: 1,
+        })
+    );
+}
+
+#[test]
+fn diff_plans_a_rebalance() {
+    let shard_map = ShardMap::uniform(&["a", "b"]).unwrap();
+    let mut target = shard_map.clone();
+    target.assign(3096, 2000, "c").unwrap();
+    target.unassign(8000, 192).unwrap();
+
+    assert_eq!(
+        shard_map.diff(&target),
+        [
+            shard_move(3096, 1000, Some("a"), Some("c")),
+            shard_move(4096, 1000, Some("b"), Some("c")),
+            shard_move(8000, 192, Some("b"), None),
+        ]
+    );
+    assert_eq!(
+        target.diff(&shard_map),
+        [
+            shard_move(3096, 1000, Some("c"), Some("a")),
+            shard_move(4096, 1000, Some("c"), Some("b")),
+            shard_move(8000, 192, None, Some("b")),
+        ]
+    );
+    assert_eq!(shard_map.diff(&shard_map), []);
+}
+
+#[test]
+fn ranges_convert_into_a_shard_map() {
+    let shard_map = ShardMap::try_from(vec![
+        shard_range(100, 100, "b"),
+        shard_range(0, 50, "a"),
+        shard_range(50, 50, "a"),
+    ])
+    .unwrap();
+    assert_eq!(
+        shard_map.ranges(),
+        [shard_range(0, 100, "a"), shard_range(100, 100, "b")]
+    );
+
+    assert_eq!(
+        ShardMap::try_from(vec![shard_range(0, 100, "a"), shard_range(50, 100, "b")]),
+        Err(Error::OverlappingShardRanges { logical_volume: 50 })
+    );
+}
@@ -1,4 +1,4 @@
-use snowprints::{compose, decompose, Error, Settings, Snowprint};
+use snowprints::{compose, decompose, try_compose, Error, Settings, Snowprint, SnowprintId};
 use std::time::{Duration, UNIX_EPOCH};
 
 const JANUARY_1ST_2024_AS_MS: u64 = 1704096000000;
@@ -37,6 +37,7 @@ fn snowprint_struct_builds_and_returns_snowprint() {
         origin_system_time: UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION,
         logical_volume_base: 0,
         logical_volume_length: 8192,
+        ..Default::default()
     };
 
     let mut builder = match Snowprint::new(settings) {
@@ -48,12 +49,110 @@ fn snowprint_struct_builds_and_returns_snowprint() {
     let snowprint = builder.compose();
     match snowprint {
         Ok(sp) => {
-            let (_timestamp, logical_volume, sequence) = decompose(sp);
-
-            assert_eq!(logical_volume, 0);
-            assert_eq!(sequence, 1);
+            assert_eq!(sp.logical_volume(), 0);
+            assert_eq!(sp.sequence(), 1);
         }
         // error by comparing result to incorrect error
-        Err(err) => assert_eq!(Error::ExceededAvailableLogicalVolumes, err),
+        Err(err) => assert_eq!(Error::LogicalVolumeModuloIsZero, err),
     }
 }
+
+#[test]
+fn snowprint_id_accessors_and_conversions() {
+    let snowprint = compose(JANUARY_1ST_2024_AS_MS, 7890, 956);
+    let snowprint_id = SnowprintId::from(snowprint);
+
+    assert_eq!(snowprint_id.timestamp_ms(), JANUARY_1ST_2024_AS_MS);
+    assert_eq!(snowprint_id.logical_volume(), 7890);
+    assert_eq!(snowprint_id.sequence(), 956);
+    assert_eq!(snowprint_id.as_u64(), snowprint);
+    assert_eq!(u64::from(snowprint_id), snowprint);
+
+    let as_system_time = snowprint_id.to_system_time(UNIX_EPOCH);
+    assert_eq!(as_system_time, UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION);
+}
+
+#[test]
+fn snowprint_ids_sort_by_time_then_logical_volume_then_sequence() {
+    let mut snowprint_ids = vec![
+        SnowprintId::from(compose(2, 0, 0)),
+        SnowprintId::from(compose(1, 1, 0)),
+        SnowprintId::from(compose(1, 0, 1)),
+        SnowprintId::from(compose(1, 0, 0)),
+    ];
+    snowprint_ids.sort();
+
+    let snowprints: Vec<u64> = snowprint_ids.into_iter().map(u64::from).collect();
+    assert_eq!(
+        snowprints,
+        vec![
+            compose(1, 0, 0),
+            compose(1, 0, 1),
+            compose(1, 1, 0),
+            compose(2, 0, 0)
+        ]
+    );
+}
+
+#[test]
+fn try_compose_accepts_the_largest_fields() {
+    let max_timestamp = (1 << 41) - 1;
+    let snowprint = try_compose(max_timestamp, 8191, 1023);
+
+    assert_eq!(snowprint, Ok(u64::MAX));
+    assert_eq!(decompose(u64::MAX), (max_timestamp, 8191, 1023));
+}
+
+#[test]
+fn try_compose_rejects_overflowing_fields() {
+    assert_eq!(
+        try_compose(1 << 41, 0, 0),
+        Err(Error::ExceededTimestampBitLength {
+            timestamp_ms: 1 << 41
+        })
+    );
+    assert_eq!(
+        try_compose(JANUARY_1ST_2024_AS_MS, 9000, 0),
+        Err(Error::ExceededLogicalVolumeBitLength {
+            logical_volume: 9000
+        })
+    );
+    assert_eq!(
+        try_compose(JANUARY_1ST_2024_AS_MS, 0, 2000),
+        Err(Error::ExceededSequenceBitLength { sequence: 2000 })
+    );
+}
+
+#[test]
+fn errors_display_their_context() {
+    let error = Error::ExceededAvailableLogicalVolumes {
+        logical_volume_base: 4096,
+        logical_volume_length: 8192,
+    };
+    assert_eq!(
+        error.to_string(),
+        "logical_volume_base 4096 + logical_volume_length 8192 exceeds 8192 available logical volumes"
+    );
+
+    let error = Error::ExceededSequenceBitLength { sequence: 2000 };
+    assert_eq!(
+        error.to_string(),
+        "sequence 2000 exceeds the maximum of 1023"
+    );
+
+    let error = Error::ClockMovedBackwards { by_ms: 42 };
+    assert_eq!(error.to_string(), "clock moved backwards by 42ms");
+}
+
+#[test]
+fn errors_convert_into_boxed_errors() {
+    fn compose_out_of_range() -> Result<u64, Box<dyn std::error::Error>> {
+        Ok(try_compose(0, 9000, 0)?)
+    }
+
+    let error = compose_out_of_range().expect_err("volume is out of range");
+    assert_eq!(
+        error.to_string(),
+        "logical volume 9000 exceeds the maximum of 8191"
+    );
+}
@@ -0,0 +1,136 @@
+#![cfg(feature = "sqlite")]
+
+use snowprints::{
+    Error, LeasedSnowprint, ManualClock, Settings, SqliteLeaseStore, VolumeLease, VolumeLeaseStore,
+};
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use std::thread;
+use std::time::{Duration, UNIX_EPOCH};
+
+const JANUARY_1ST_2024_AS_MS: u64 = 1704096000000;
+const JANUARY_1ST_2024_AS_DURATION: Duration = Duration::from_millis(JANUARY_1ST_2024_AS_MS);
+
+fn temp_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("snowprints-sqlite-{}-{}", name, process::id()));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn sqlite_lease_store_leases_disjoint_volumes() {
+    let mut store = SqliteLeaseStore::open(":memory:", 100, 8).unwrap();
+
+    let first = store.acquire("first", 4, 100, 1000).unwrap();
+    let second = store.acquire("second", 3, 100, 1000).unwrap();
+    assert_eq!(first.logical_volume_base, 100);
+    assert_eq!(second.logical_volume_base, 104);
+    assert_eq!(
+        store.acquire("third", 2, 100, 1000),
+        Err(Error::NoLogicalVolumesToLease {
+            logical_volume_length: 2
+        })
+    );
+
+    store
+        .release(&VolumeLease {
+            expires_at_ms: 1001,
+            ..first.clone()
+        })
+        .unwrap();
+    assert_eq!(
+        store.acquire("third", 2, 99, 1000),
+        Err(Error::NoLogicalVolumesToLease {
+            logical_volume_length: 2
+        })
+    );
+    let third = store.acquire("third", 2, 99, 1001).unwrap();
+    assert_eq!(third.logical_volume_base, 100);
+
+    let second = store.heartbeat(&second, 500, 1050).unwrap();
+    assert_eq!(second.expires_at_ms, 1550);
+    assert_eq!(
+        store.heartbeat(&third, 100, 1100),
+        Err(Error::LeaseLapsed {
+            expires_at_ms: 1100
+        })
+    );
+
+    let fourth = store.acquire("fourth", 4, 100, 1100).unwrap();
+    assert_eq!(fourth.logical_volume_base, 100);
+    assert!(matches!(
+        store.heartbeat(&first, 100, 1100),
+        Err(Error::LeaseLapsed { .. })
+    ));
+    assert_eq!(
+        store.acquire("fifth", 1, 0, 1100),
+        Err(Error::LeaseTtlIsZero)
+    );
+    assert_eq!(
+        store.acquire("fifth", 1, i64::MAX as u64, 1100),
+        Err(Error::ExceededLeaseTtl {
+            ttl_ms: i64::MAX as u64
+        })
+    );
+    assert_eq!(
+        store.heartbeat(&fourth, u64::MAX, 1100),
+        Err(Error::ExceededLeaseTtl { ttl_ms: u64::MAX })
+    );
+}
+
+#[test]
+fn sqlite_lease_store_is_shared_across_connections() {
+    let path = temp_path("connections");
+
+    let handles: Vec<_> = (0..8)
+        .map(|index| {
+            let path = path.clone();
+            thread::spawn(move || {
+                let mut store = SqliteLeaseStore::open(&path, 0, 64).unwrap();
+                store
+                    .acquire(&format!("holder-{}", index), 8, 10_000, 1000)
+                    .unwrap()
+            })
+        })
+        .collect();
+
+    let mut bases = HashSet::new();
+    for handle in handles {
+        assert!(bases.insert(handle.join().unwrap().logical_volume_base));
+    }
+    assert_eq!(bases.len(), 8);
+
+    let _ = fs::remove_file(&path);
+}
+
+#[test]
+fn leased_snowprint_composes_on_sqlite_leases() {
+    let path = temp_path("snowprint");
+    let clock =
+        ManualClock::new(UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION + Duration::from_secs(60));
+    let settings = Settings {
+        origin_system_time: UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION,
+        logical_volume_length: 4,
+        ..Default::default()
+    };
+
+    let store = SqliteLeaseStore::open(&path, 2048, 8).unwrap();
+    let mut snowprinter =
+        LeasedSnowprint::with_clock(settings.clone(), store, "first", 100, clock.clone()).unwrap();
+    let snowprint = snowprinter.compose().unwrap();
+    assert!((2048..2052).contains(&snowprint.logical_volume()));
+
+    let store = SqliteLeaseStore::open(&path, 2048, 8).unwrap();
+    let other = LeasedSnowprint::with_clock(settings, store, "second", 100, clock.clone()).unwrap();
+    assert_eq!(other.lease().logical_volume_base, 2052);
+
+    clock.advance(Duration::from_millis(100));
+    assert!(matches!(
+        snowprinter.compose(),
+        Err(Error::LeaseLapsed { .. })
+    ));
+
+    let _ = fs::remove_file(&path);
+}
@@ -0,0 +1,229 @@
+use snowprints::{
+    AuditSettings, Auditor, Error, ExhaustionPolicy, LogicalVolumeSet, ManualClock, Settings,
+    Snowprint, SnowprintId, SyncSnowprint,
+};
+use std::time::{Duration, UNIX_EPOCH};
+
+const JANUARY_1ST_2024_AS_MS: u64 = 1704096000000;
+const JANUARY_1ST_2024_AS_DURATION: Duration = Duration::from_millis(JANUARY_1ST_2024_AS_MS);
+
+fn settings(logical_volume_length: u64, strictly_increasing: bool) -> Settings {
+    Settings {
+        origin_system_time: UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION,
+        logical_volume_length,
+        strictly_increasing,
+        ..Default::default()
+    }
+}
+
+fn clock() -> ManualClock {
+    ManualClock::new(UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION)
+}
+
+// composes until the current ms is exhausted
+fn compose_ms(snowprinter: &mut Snowprint<ManualClock>) -> Vec<SnowprintId> {
+    let mut snowprints = Vec::new();
+    loop {
+        match snowprinter.compose() {
+            Ok(snowprint) => snowprints.push(snowprint),
+            Err(Error::ExceededAvailableSequences) => return snowprints,
+            Err(err) => panic!("snowprint should compose: {:?}", err),
+        }
+    }
+}
+
+#[test]
+fn rotation_wraps_to_a_smaller_snowprint_by_default() {
+    let clock = clock();
+    let mut snowprinter = Snowprint::with_clock(settings(4, false), clock.clone()).unwrap();
+    clock.advance(Duration::from_millis(1));
+    snowprinter.compose().unwrap();
+    clock.advance(Duration::from_millis(1));
+
+    // the ms starts on logical volume 2 and wraps back to 0
+    let snowprints = compose_ms(&mut snowprinter);
+    assert_eq!(snowprints.len(), 3 * 1024);
+    assert!(snowprints.windows(2).any(|pair| pair[1] < pair[0]));
+}
+
+#[test]
+fn strictly_increasing_stops_before_wrapping() {
+    let clock = clock();
+    let mut snowprinter = Snowprint::with_clock(settings(4, true), clock.clone()).unwrap();
+    clock.advance(Duration::from_millis(1));
+    snowprinter.compose().unwrap();
+    clock.advance(Duration::from_millis(1));
+
+    // the ms starts on logical volume 2 and stops after 3
+    let snowprints = compose_ms(&mut snowprinter);
+    assert_eq!(snowprints.len(), 2 * 1024);
+    assert!(snowprints.windows(2).all(|pair| pair[0] < pair[1]));
+    assert_eq!(snowprints[0].logical_volume(), 2);
+    assert_eq!(snowprints[snowprints.len() - 1].logical_volume(), 3);
+}
+
+#[test]
+fn strictly_increasing_trades_capacity_for_order() {
+    let clock = clock();
+    let mut snowprinter = Snowprint::with_clock(settings(4, true), clock.clone()).unwrap();
+    let mut auditor = Auditor::with_clock(
+        AuditSettings {
+            origin_system_time: UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION,
+            ..Default::default()
+        },
+        clock.clone(),
+    );
+
+    // at low load each ms starts on the next logical volume, same as the default
+    let mut logical_volumes = Vec::new();
+    for _ in 0..8 {
+        clock.advance(Duration::from_millis(1));
+        let snowprint = snowprinter.compose().unwrap();
+        logical_volumes.push(snowprint.logical_volume());
+        assert!(auditor.observe("strict", snowprint).is_empty());
+    }
+    assert_eq!(logical_volumes, [1, 2, 3, 0, 1, 2, 3, 0]);
+
+    // an exhausted ms ends on the last logical volume, so the next starts on the first,
+    // and a ms that starts on logical volume k only fits 4 - k logical volumes
+    let mut capacities = Vec::new();
+    let mut first_logical_volumes = Vec::new();
+    for _ in 0..4 {
+        clock.advance(Duration::from_millis(1));
+        let snowprints = compose_ms(&mut snowprinter);
+        capacities.push(snowprints.len() / 1024);
+        first_logical_volumes.push(snowprints[0].logical_volume());
+        for snowprint in snowprints {
+            assert!(auditor.observe("strict", snowprint).is_empty());
+        }
+    }
+    assert_eq!(capacities, [3, 4, 4, 4]);
+    assert_eq!(first_logical_volumes, [1, 0, 0, 0]);
+    assert!(auditor.report().is_clean());
+}
+
+#[test]
+fn strictly_increasing_reserves_within_the_ms() {
+    let clock = clock();
+    let mut snowprinter = Snowprint::with_clock(settings(4, true), clock.clone()).unwrap();
+    clock.advance(Duration::from_millis(2));
+
+    // the ms starts on logical volume 1, so 3 logical volumes fit
+    assert_eq!(
+        snowprinter.reserve(3 * 1024 + 1),
+        Err(Error::ExceededAvailableSequences)
+    );
+    let snowprints: Vec<SnowprintId> = snowprinter.reserve(3 * 1024).unwrap().collect();
+    assert!(snowprints.windows(2).all(|pair| pair[0] < pair[1]));
+    assert_eq!(
+        snowprinter.compose(),
+        Err(Error::ExceededAvailableSequences)
+    );
+}
+
+#[test]
+fn strictly_increasing_moves_the_rotation_for_chosen_logical_volumes() {
+    let clock = clock();
+    let mut snowprinter = Snowprint::with_clock(settings(4, true), clock.clone()).unwrap();
+    clock.advance(Duration::from_millis(1));
+
+    let parent = snowprinter.compose().unwrap();
+    assert_eq!(parent.logical_volume(), 1);
+
+    // a logical volume ahead of the rotation skips the rotation forward
+    let child = snowprinter
+        .compose_child_of(SnowprintId::from(3 << 10))
+        .unwrap();
+    assert_eq!(child.logical_volume(), 3);
+    assert!(parent < child);
+    let next = snowprinter.compose().unwrap();
+    assert_eq!((next.logical_volume(), next.sequence()), (3, 1));
+
+    // a logical volume behind the rotation waits for the next ms
+    assert_eq!(
+        snowprinter.compose_child_of(parent),
+        Err(Error::ExceededAvailableSequences)
+    );
+    clock.advance(Duration::from_millis(1));
+    let child = snowprinter.compose_child_of(parent).unwrap();
+    assert_eq!((child.logical_volume(), child.sequence()), (1, 0));
+    assert!(next < child);
+    let next = snowprinter.compose().unwrap();
+    assert_eq!((next.logical_volume(), next.sequence()), (1, 1));
+}
+
+#[test]
+fn strictly_increasing_keys_never_go_backwards() {
+    let clock = clock();
+    let mut snowprinter = Snowprint::with_clock(settings(16, true), clock.clone()).unwrap();
+
+    let mut prev = snowprinter.compose().unwrap();
+    for index in 0..2000u64 {
+        if index % 50 == 0 {
+            clock.advance(Duration::from_millis(1));
+        }
+        let snowprint = match index % 3 {
+            0 => snowprinter.compose().unwrap(),
+            // keys behind the rotation fail instead of waiting on a manual clock
+            _ => match snowprinter.compose_for_key(index.to_be_bytes()) {
+                Ok(snowprint) => snowprint,
+                Err(_) => continue,
+            },
+        };
+        assert!(prev < snowprint);
+        prev = snowprint;
+    }
+}
+
+#[test]
+fn strictly_increasing_sync_snowprint_stops_before_wrapping() {
+    let clock = clock();
+    let snowprinter = SyncSnowprint::with_clock(settings(4, true), clock.clone()).unwrap();
+
+    for expected_len in [3, 4, 4] {
+        clock.advance(Duration::from_millis(1));
+        let mut snowprints = Vec::new();
+        while let Ok(snowprint) = snowprinter.compose() {
+            snowprints.push(snowprint);
+        }
+        assert_eq!(snowprints.len(), expected_len * 1024);
+        assert!(snowprints.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+}
+
+#[test]
+fn strictly_increasing_under_load() {
+    let settings = Settings {
+        exhaustion_policy: ExhaustionPolicy::Spin,
+        ..settings(2, true)
+    };
+    let mut snowprinter = Snowprint::new(settings.clone()).unwrap();
+    let snowprints = snowprinter.compose_batch(20_000).unwrap();
+    assert!(snowprints.windows(2).all(|pair| pair[0] < pair[1]));
+
+    let snowprinter = SyncSnowprint::new(settings).unwrap();
+    let mut prev = snowprinter.compose().unwrap();
+    for _ in 0..20_000 {
+        let snowprint = snowprinter.compose().unwrap();
+        assert!(prev < snowprint);
+        prev = snowprint;
+    }
+}
+
+#[test]
+fn strictly_increasing_requires_an_ascending_set() {
+    let unordered = Settings {
+        logical_volume_set: Some(LogicalVolumeSet::new([3, 70, 5])),
+        ..settings(1, true)
+    };
+    assert_eq!(
+        Snowprint::new(unordered).err(),
+        Some(Error::UnorderedLogicalVolumeSet { logical_volume: 5 })
+    );
+
+    let ordered = Settings {
+        logical_volume_set: Some(LogicalVolumeSet::new([3, 5, 70])),
+        ..settings(1, true)
+    };
+    assert!(Snowprint::new(ordered).is_ok());
+}
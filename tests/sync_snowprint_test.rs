@@ -0,0 +1,58 @@
+use snowprints::{Settings, SyncSnowprint};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, UNIX_EPOCH};
+
+const JANUARY_1ST_2024_AS_MS: u64 = 1704096000000;
+const JANUARY_1ST_2024_AS_DURATION: Duration = Duration::from_millis(JANUARY_1ST_2024_AS_MS);
+
+#[test]
+fn sync_snowprint_builds_and_returns_snowprint() {
+    let settings = Settings {
+        origin_system_time: UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION,
+        logical_volume_base: 0,
+        logical_volume_length: 8192,
+        ..Default::default()
+    };
+
+    let snowprinter = SyncSnowprint::new(settings).expect("settings should be valid");
+    let snowprint = snowprinter.compose().expect("snowprint should compose");
+
+    assert!(snowprint.logical_volume() < 2);
+    assert!(snowprint.sequence() < 2);
+}
+
+#[test]
+fn sync_snowprint_composes_unique_snowprints_across_threads() {
+    let settings = Settings {
+        origin_system_time: UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION,
+        logical_volume_base: 2048,
+        logical_volume_length: 2048,
+        ..Default::default()
+    };
+    let snowprinter = Arc::new(SyncSnowprint::new(settings).expect("settings should be valid"));
+
+    let mut handles = Vec::new();
+    for _ in 0..8 {
+        let snowprinter = snowprinter.clone();
+        handles.push(thread::spawn(move || {
+            let mut snowprints = Vec::new();
+            while snowprints.len() < 10000 {
+                if let Ok(snowprint) = snowprinter.compose() {
+                    snowprints.push(snowprint);
+                }
+            }
+            snowprints
+        }));
+    }
+
+    let mut unique = HashSet::new();
+    for handle in handles {
+        for snowprint in handle.join().expect("thread should not panic") {
+            assert!((2048..4096).contains(&snowprint.logical_volume()));
+            assert!(unique.insert(snowprint));
+        }
+    }
+    assert_eq!(unique.len(), 80000);
+}
@@ -0,0 +1,163 @@
+use snowprints::{
+    Error, LogicalVolumeSet, ManualClock, Settings, Snowprint, SyncSnowprint, WeightedLogicalVolume,
+};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, UNIX_EPOCH};
+
+const JANUARY_1ST_2024_AS_MS: u64 = 1704096000000;
+const JANUARY_1ST_2024_AS_DURATION: Duration = Duration::from_millis(JANUARY_1ST_2024_AS_MS);
+
+fn settings(logical_volume_set: LogicalVolumeSet) -> Settings {
+    Settings {
+        origin_system_time: UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION,
+        logical_volume_set: Some(logical_volume_set),
+        ..Default::default()
+    }
+}
+
+fn weighted(logical_volume: u64, weight: u64) -> WeightedLogicalVolume {
+    WeightedLogicalVolume {
+        logical_volume,
+        weight,
+    }
+}
+
+#[test]
+fn check_settings_validates_logical_volume_sets() {
+    let clock = ManualClock::new(UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION);
+    let check = |logical_volume_set| {
+        Snowprint::with_clock(settings(logical_volume_set), clock.clone()).err()
+    };
+
+    assert_eq!(check(LogicalVolumeSet::new([3, 70, 5])), None);
+    assert_eq!(
+        check(LogicalVolumeSet::new([])),
+        Some(Error::EmptyLogicalVolumeSet)
+    );
+    assert_eq!(
+        check(LogicalVolumeSet::new([3, 8192])),
+        Some(Error::ExceededLogicalVolumeBitLength {
+            logical_volume: 8192
+        })
+    );
+    assert_eq!(
+        check(LogicalVolumeSet::new([3, 5, 3])),
+        Some(Error::DuplicateLogicalVolume { logical_volume: 3 })
+    );
+    assert_eq!(
+        check(LogicalVolumeSet::weighted([weighted(3, 2), weighted(5, 0)])),
+        Some(Error::LogicalVolumeWeightIsZero { logical_volume: 5 })
+    );
+    assert_eq!(
+        check(LogicalVolumeSet::weighted([weighted(3, 0), weighted(5, 0)])),
+        Some(Error::LogicalVolumeWeightIsZero { logical_volume: 3 })
+    );
+    assert_eq!(
+        check(LogicalVolumeSet::weighted([
+            weighted(3, u64::MAX),
+            weighted(5, 1)
+        ])),
+        Some(Error::ExceededTotalLogicalVolumeWeight)
+    );
+}
+
+#[test]
+fn logical_volume_set_replaces_base_and_length() {
+    let settings = Settings {
+        logical_volume_base: 8000,
+        logical_volume_length: 8000,
+        ..settings(LogicalVolumeSet::new([3, 70, 5]))
+    };
+    let clock = ManualClock::new(UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION);
+    let mut snowprinter = Snowprint::with_clock(settings, clock.clone()).unwrap();
+
+    let mut logical_volumes = Vec::new();
+    for _ in 0..6 {
+        clock.advance(Duration::from_millis(1));
+        logical_volumes.push(snowprinter.compose().unwrap().logical_volume());
+    }
+    assert_eq!(logical_volumes, [70, 5, 3, 70, 5, 3]);
+}
+
+#[test]
+fn logical_volume_set_exhausts_without_duplicates() {
+    let logical_volume_sets = [
+        LogicalVolumeSet::new([3, 70, 5]),
+        LogicalVolumeSet::weighted([weighted(3, 1), weighted(70, 5), weighted(5, 2)]),
+    ];
+    for logical_volume_set in logical_volume_sets {
+        let clock = ManualClock::new(UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION);
+        let mut snowprinter =
+            Snowprint::with_clock(settings(logical_volume_set), clock.clone()).unwrap();
+
+        for _ in 0..16 {
+            clock.advance(Duration::from_millis(1));
+            let mut unique = HashSet::new();
+            while let Ok(snowprint) = snowprinter.compose() {
+                assert!([3, 70, 5].contains(&snowprint.logical_volume()));
+                assert!(unique.insert(snowprint));
+            }
+            assert_eq!(unique.len(), 2 * 1024);
+        }
+    }
+}
+
+#[test]
+fn weights_favor_heavier_logical_volumes() {
+    let logical_volume_set =
+        LogicalVolumeSet::weighted([weighted(3, 1), weighted(70, 6), weighted(5, 3)]);
+    let clock = ManualClock::new(UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION);
+    let mut snowprinter =
+        Snowprint::with_clock(settings(logical_volume_set), clock.clone()).unwrap();
+
+    let mut counts: HashMap<u64, u64> = HashMap::new();
+    for _ in 0..10000 {
+        clock.advance(Duration::from_millis(1));
+        *counts
+            .entry(snowprinter.compose().unwrap().logical_volume())
+            .or_default() += 1;
+    }
+
+    assert!((700..1300).contains(&counts[&3]));
+    assert!((5500..6500).contains(&counts[&70]));
+    assert!((2500..3500).contains(&counts[&5]));
+}
+
+#[test]
+fn equal_weights_rotate_evenly() {
+    let logical_volume_set = LogicalVolumeSet::weighted([weighted(3, 4), weighted(70, 4)]);
+    assert_eq!(logical_volume_set, LogicalVolumeSet::new([3, 70]));
+    assert_eq!(logical_volume_set.weight(0), 1);
+}
+
+#[test]
+fn sync_snowprint_follows_logical_volume_sets() {
+    let logical_volume_set =
+        LogicalVolumeSet::weighted([weighted(3, 1), weighted(70, 6), weighted(5, 3)]);
+    let clock = ManualClock::new(UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION);
+    let mut snowprinter =
+        Snowprint::with_clock(settings(logical_volume_set.clone()), clock.clone()).unwrap();
+    let sync_snowprinter =
+        SyncSnowprint::with_clock(settings(logical_volume_set), clock.clone()).unwrap();
+
+    for step in 0..200 {
+        if step % 3 == 0 {
+            clock.advance(Duration::from_millis(1));
+        }
+        assert_eq!(sync_snowprinter.compose(), snowprinter.compose());
+    }
+}
+
+#[test]
+fn reserve_follows_logical_volume_sets() {
+    let logical_volume_set = LogicalVolumeSet::new([3, 70, 5]);
+    let clock = ManualClock::new(UNIX_EPOCH + JANUARY_1ST_2024_AS_DURATION);
+    let mut snowprinter =
+        Snowprint::with_clock(settings(logical_volume_set.clone()), clock.clone()).unwrap();
+    let mut expected = Snowprint::with_clock(settings(logical_volume_set), clock.clone()).unwrap();
+
+    clock.advance(Duration::from_millis(1));
+    for reserved in snowprinter.reserve(1500).unwrap() {
+        assert_eq!(Ok(reserved), expected.compose());
+    }
+}
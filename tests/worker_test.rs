@@ -0,0 +1,113 @@
+use snowprints::{Error, Settings, Snowprint};
+use std::env;
+use std::net::IpAddr;
+
+#[test]
+fn for_worker_rotates_a_disjoint_range() {
+    let settings = Settings::for_worker(0, 64).unwrap();
+    assert_eq!(settings.logical_volume_base, 0);
+    assert_eq!(settings.logical_volume_length, 64);
+
+    let settings = Settings::for_worker(3, 64).unwrap();
+    assert_eq!(settings.logical_volume_base, 192);
+    assert_eq!(settings.logical_volume_length, 64);
+
+    let settings = Settings::for_worker(127, 64).unwrap();
+    assert_eq!(settings.logical_volume_base, 8128);
+    assert!(Snowprint::new(settings).is_ok());
+}
+
+#[test]
+fn for_worker_checks_the_range_fits() {
+    assert_eq!(
+        Settings::for_worker(128, 64),
+        Err(Error::ExceededAvailableLogicalVolumes {
+            logical_volume_base: 8192,
+            logical_volume_length: 64,
+        })
+    );
+    assert_eq!(
+        Settings::for_worker(u64::MAX, 2),
+        Err(Error::ExceededAvailableLogicalVolumes {
+            logical_volume_base: u64::MAX,
+            logical_volume_length: 2,
+        })
+    );
+    assert_eq!(
+        Settings::for_worker(0, 0),
+        Err(Error::LogicalVolumeModuloIsZero)
+    );
+}
+
+#[test]
+fn for_worker_from_env_reads_a_worker_id() {
+    env::set_var("SNOWPRINTS_TEST_WORKER_ID", "5");
+    let settings = Settings::for_worker_from_env("SNOWPRINTS_TEST_WORKER_ID", 16).unwrap();
+    assert_eq!(settings.logical_volume_base, 80);
+
+    env::set_var("SNOWPRINTS_TEST_WORKER_ID_BAD", "five");
+    assert_eq!(
+        Settings::for_worker_from_env("SNOWPRINTS_TEST_WORKER_ID_BAD", 16),
+        Err(Error::FailedToParseWorkerId)
+    );
+    assert_eq!(
+        Settings::for_worker_from_env("SNOWPRINTS_TEST_WORKER_ID_UNSET", 16),
+        Err(Error::MissingWorkerId)
+    );
+}
+
+#[test]
+fn for_worker_from_hostname_reads_a_statefulset_ordinal() {
+    let settings = Settings::for_worker_from_hostname("snowprints-0", 32).unwrap();
+    assert_eq!(settings.logical_volume_base, 0);
+
+    let settings = Settings::for_worker_from_hostname("id-service-12", 32).unwrap();
+    assert_eq!(settings.logical_volume_base, 384);
+
+    let settings =
+        Settings::for_worker_from_hostname("web-3.nginx.default.svc.cluster.local", 32).unwrap();
+    assert_eq!(settings.logical_volume_base, 96);
+
+    for hostname in ["snowprints", "snowprints-", "snowprints-a1", ""] {
+        assert_eq!(
+            Settings::for_worker_from_hostname(hostname, 32),
+            Err(Error::FailedToParseWorkerId)
+        );
+    }
+    assert!(matches!(
+        Settings::for_worker_from_hostname("snowprints-256", 32),
+        Err(Error::ExceededAvailableLogicalVolumes { .. })
+    ));
+}
+
+#[test]
+fn for_worker_from_ip_uses_the_low_bits() {
+    // 8192 / 32 leaves 256 workers, the last octet
+    let ip: IpAddr = "10.0.3.7".parse().unwrap();
+    let settings = Settings::for_worker_from_ip(ip, 32).unwrap();
+    assert_eq!(settings.logical_volume_base, 7 * 32);
+
+    // 8192 / 1 leaves 8192 workers, the low 13 bits
+    let settings = Settings::for_worker_from_ip(ip, 1).unwrap();
+    assert_eq!(settings.logical_volume_base, 3 * 256 + 7);
+
+    // 8192 / 3 leaves 2730 workers, 11 bits fit
+    let settings = Settings::for_worker_from_ip(ip, 3).unwrap();
+    assert_eq!(settings.logical_volume_base, (3 * 256 + 7) * 3);
+
+    let ip: IpAddr = "fd00::1:ff".parse().unwrap();
+    let settings = Settings::for_worker_from_ip(ip, 64).unwrap();
+    assert_eq!(settings.logical_volume_base, 127 * 64);
+
+    let settings = Settings::for_worker_from_ip(ip, 8192).unwrap();
+    assert_eq!(settings.logical_volume_base, 0);
+
+    assert_eq!(
+        Settings::for_worker_from_ip(ip, 0),
+        Err(Error::LogicalVolumeModuloIsZero)
+    );
+    assert!(matches!(
+        Settings::for_worker_from_ip(ip, 8193),
+        Err(Error::ExceededAvailableLogicalVolumes { .. })
+    ));
+}